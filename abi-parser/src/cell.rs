@@ -0,0 +1,316 @@
+use std::str::FromStr;
+
+use pest::iterators::Pair;
+use pest::Parser;
+use ton_types::{BuilderData, Cell, CellType, HashmapE, HashmapType, SliceData};
+
+use crate::ParserError;
+
+const PRUNED_BRANCH_TAG: u8 = 1;
+const LIBRARY_REFERENCE_TAG: u8 = 2;
+const MERKLE_PROOF_TAG: u8 = 3;
+const MERKLE_UPDATE_TAG: u8 = 4;
+
+#[derive(pest_derive::Parser)]
+#[grammar = "cell.pest"]
+struct Grammar;
+
+/// Builds a cell tree from the Fift-like notation.
+///
+/// The root cell consists of the listed items:
+/// - `x{ABC_}` and `b{0101}` append hex (with an optional completion tag) and binary data;
+/// - `u32(10)` and `i8(-1)` append integers of the specified bit length;
+/// - `{ ... }` appends a reference to an ordinary cell with the nested items;
+/// - `dict(32) { 1: { ... } 2: { ... } }` appends a dictionary with the literal values;
+/// - `library{hash}`, `pruned{hash:depth ...}` and `exotic{ ... }` append a reference
+///   to an exotic cell. When used alone at the top level, they describe the root itself.
+pub fn build_cell(input: &str) -> Result<Cell, ParserError> {
+    let pairs = Grammar::parse(Rule::cell, input.trim())
+        .map_err(|e| ParserError::InvalidInput(e.to_string()))?;
+
+    let root = pairs.into_iter().next().ok_or(ParserError::UnexpectedEof)?;
+    let mut items = root.into_inner().peekable();
+    match items.peek().map(Pair::as_rule) {
+        Some(Rule::library | Rule::pruned | Rule::exotic) if items.clone().count() == 1 => {
+            build_special(items.next().ok_or(ParserError::UnexpectedEof)?)
+        }
+        _ => finalize(build_items(items)?),
+    }
+}
+
+/// Parses data in the Fift hex notation (with an optional `_` completion tag).
+pub fn parse_hex_slice(data: &str) -> Result<BuilderData, ParserError> {
+    let (digits, completion) = match data.strip_suffix('_') {
+        Some(digits) => (digits, true),
+        None => (data, false),
+    };
+
+    let mut bits = Vec::with_capacity(digits.len() * 4);
+    for digit in digits.chars() {
+        let nibble = digit
+            .to_digit(16)
+            .ok_or_else(|| ParserError::InvalidCell(format!("invalid hex digit: {digit}")))?;
+        bits.extend((0..4).rev().map(|i| nibble & (1 << i) != 0));
+    }
+
+    if completion {
+        while let Some(false) = bits.last() {
+            bits.pop();
+        }
+        if bits.pop().is_none() {
+            return Err(ParserError::InvalidCell(
+                "missing completion bit".to_string(),
+            ));
+        }
+    }
+
+    let mut builder = BuilderData::new();
+    append_bits(&mut builder, &bits)?;
+    Ok(builder)
+}
+
+fn build_items<'a>(
+    items: impl Iterator<Item = Pair<'a, Rule>>,
+) -> Result<BuilderData, ParserError> {
+    let mut builder = BuilderData::new();
+    for item in items {
+        match item.as_rule() {
+            Rule::hex_slice => {
+                let data = item.into_inner().next().ok_or(ParserError::UnexpectedEof)?;
+                let slice = parse_hex_slice(data.as_str())?;
+                builder.append_builder(&slice).map_err(cell_error)?;
+            }
+            Rule::bin_slice => {
+                let data = item.into_inner().next().ok_or(ParserError::UnexpectedEof)?;
+                let bits = data.as_str().chars().map(|c| c == '1').collect::<Vec<_>>();
+                append_bits(&mut builder, &bits)?;
+            }
+            Rule::uint_literal | Rule::int_literal => {
+                let signed = item.as_rule() == Rule::int_literal;
+                let mut rules = item.into_inner();
+                let bits = parse_nat(rules.next())?;
+                let value = parse_number(rules.next())?;
+                append_int(&mut builder, value, bits, signed)?;
+            }
+            Rule::ordinary => {
+                let child = finalize(build_items(item.into_inner())?)?;
+                builder
+                    .checked_append_reference(child)
+                    .map_err(cell_error)?;
+            }
+            Rule::library | Rule::pruned | Rule::exotic => {
+                let child = build_special(item)?;
+                builder
+                    .checked_append_reference(child)
+                    .map_err(cell_error)?;
+            }
+            Rule::dict => build_dict(&mut builder, item)?,
+            rule => return Err(ParserError::UnexpectedRule(format!("{rule:?}"))),
+        }
+    }
+    Ok(builder)
+}
+
+fn build_special(item: Pair<Rule>) -> Result<Cell, ParserError> {
+    let mut builder = BuilderData::new();
+    let cell_type = match item.as_rule() {
+        Rule::library => {
+            let hash = item.into_inner().next().ok_or(ParserError::UnexpectedEof)?;
+            append_int(&mut builder, LIBRARY_REFERENCE_TAG as i128, 8, false)?;
+            builder
+                .append_builder(&parse_hex_slice(hash.as_str())?)
+                .map_err(cell_error)?;
+            CellType::LibraryReference
+        }
+        Rule::pruned => {
+            let mut hashes = BuilderData::new();
+            let mut depths = BuilderData::new();
+            let mut count = 0;
+            for entry in item.into_inner() {
+                let mut rules = entry.into_inner();
+                let hash = rules.next().ok_or(ParserError::UnexpectedEof)?;
+                hashes
+                    .append_builder(&parse_hex_slice(hash.as_str())?)
+                    .map_err(cell_error)?;
+                append_int(&mut depths, parse_nat(rules.next())? as i128, 16, false)?;
+                count += 1;
+            }
+            if count > 3 {
+                return Err(ParserError::InvalidCell(
+                    "pruned branch can have at most 3 levels".to_string(),
+                ));
+            }
+
+            append_int(&mut builder, PRUNED_BRANCH_TAG as i128, 8, false)?;
+            append_int(&mut builder, (1 << count) - 1, 8, false)?;
+            builder.append_builder(&hashes).map_err(cell_error)?;
+            builder.append_builder(&depths).map_err(cell_error)?;
+            CellType::PrunedBranch
+        }
+        Rule::exotic => {
+            builder = build_items(item.into_inner())?;
+            match builder.data().first().copied() {
+                Some(PRUNED_BRANCH_TAG) => CellType::PrunedBranch,
+                Some(LIBRARY_REFERENCE_TAG) => CellType::LibraryReference,
+                Some(MERKLE_PROOF_TAG) => CellType::MerkleProof,
+                Some(MERKLE_UPDATE_TAG) => CellType::MerkleUpdate,
+                _ => {
+                    return Err(ParserError::InvalidCell(
+                        "exotic cell must start with a known type tag".to_string(),
+                    ))
+                }
+            }
+        }
+        rule => return Err(ParserError::UnexpectedRule(format!("{rule:?}"))),
+    };
+
+    builder.set_type(cell_type);
+    finalize(builder)
+}
+
+fn build_dict(builder: &mut BuilderData, item: Pair<Rule>) -> Result<(), ParserError> {
+    let mut rules = item.into_inner();
+    let key_bits = parse_nat(rules.next())?;
+
+    let mut dict = HashmapE::with_bit_len(key_bits);
+    for entry in rules {
+        let mut rules = entry.into_inner();
+        let key = parse_number(rules.next())?;
+
+        let mut key_builder = BuilderData::new();
+        append_int(&mut key_builder, key, key_bits, false)?;
+        let key = SliceData::load_builder(key_builder).map_err(cell_error)?;
+
+        let value = build_items(rules)?;
+        dict.set_builder(key, &value).map_err(cell_error)?;
+    }
+
+    match dict.data() {
+        Some(root) => {
+            append_bits(builder, &[true])?;
+            builder
+                .checked_append_reference(root.clone())
+                .map_err(cell_error)?;
+        }
+        None => append_bits(builder, &[false])?,
+    }
+    Ok(())
+}
+
+fn append_bits(builder: &mut BuilderData, bits: &[bool]) -> Result<(), ParserError> {
+    for bit in bits {
+        builder.append_bit_bool(*bit).map_err(cell_error)?;
+    }
+    Ok(())
+}
+
+fn append_int(
+    builder: &mut BuilderData,
+    value: i128,
+    bits: usize,
+    signed: bool,
+) -> Result<(), ParserError> {
+    let fits = match (signed, bits) {
+        (_, 0) => value == 0,
+        // `1 << 127` overflows `i128`, so any non-negative value fits into 127+ bits
+        (false, 1..=126) => value >= 0 && value < 1 << bits,
+        (false, _) => value >= 0,
+        (true, 1..=127) => value >= -(1 << (bits - 1)) && value < 1 << (bits - 1),
+        (true, _) => true,
+    };
+    if !fits || bits > 1023 {
+        return Err(ParserError::InvalidCell(format!(
+            "{value} doesn't fit into {bits} bits"
+        )));
+    }
+
+    let bits = (0..bits)
+        .rev()
+        .map(|i| {
+            if i < 128 {
+                value >> i & 1 != 0
+            } else {
+                value < 0
+            }
+        })
+        .collect::<Vec<_>>();
+    append_bits(builder, &bits)
+}
+
+fn finalize(builder: BuilderData) -> Result<Cell, ParserError> {
+    builder.into_cell().map_err(cell_error)
+}
+
+fn parse_nat(rule: Option<Pair<Rule>>) -> Result<usize, ParserError> {
+    let rule = rule.ok_or(ParserError::UnexpectedEof)?;
+    usize::from_str(rule.as_str()).map_err(|_| ParserError::InvalidTypeParam)
+}
+
+fn parse_number(rule: Option<Pair<Rule>>) -> Result<i128, ParserError> {
+    let rule = rule.ok_or(ParserError::UnexpectedEof)?;
+    let number = rule.as_str();
+    let (negative, number) = match number.strip_prefix('-') {
+        Some(number) => (true, number),
+        None => (false, number),
+    };
+    let value = match number.strip_prefix("0x") {
+        Some(hex) => i128::from_str_radix(hex, 16),
+        None => i128::from_str(number),
+    }
+    .map_err(|_| ParserError::InvalidCell(format!("invalid number: {}", rule.as_str())))?;
+    Ok(if negative { -value } else { value })
+}
+
+fn cell_error<E: ToString>(e: E) -> ParserError {
+    ParserError::InvalidCell(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn correct_cell_builder() {
+        let cell = build_cell("x{4_}").unwrap();
+        assert_eq!(cell.bit_length(), 1);
+
+        let cell =
+            build_cell("u8(255) i8(-1) b{101} { x{ABCD} } dict(32) { 1: { u8(1) } }").unwrap();
+        assert_eq!(cell.bit_length(), 20);
+        assert_eq!(cell.references_count(), 2);
+        assert_eq!(cell.data()[..2], [0xff, 0xff]);
+
+        let cell = build_cell(&format!("library{{{}}}", "00".repeat(32))).unwrap();
+        assert_eq!(cell.cell_type(), CellType::LibraryReference);
+
+        assert!(build_cell("u8(256)").is_err());
+    }
+
+    #[test]
+    fn wide_integers() {
+        const I127_MAX: &str = "85070591730234615865843651857942052863";
+        const I127_MIN: &str = "-85070591730234615865843651857942052864";
+        const I128_MAX: &str = "170141183460469231731687303715884105727";
+
+        let cell = build_cell(&format!("u127({I128_MAX})")).unwrap();
+        assert_eq!(cell.bit_length(), 127);
+        assert_eq!(cell.data()[..15], [0xff; 15]);
+        assert!(build_cell("u127(-1)").is_err());
+
+        let cell = build_cell(&format!("u128({I128_MAX})")).unwrap();
+        assert_eq!(cell.bit_length(), 128);
+        assert_eq!(cell.data()[0], 0x7f);
+
+        assert!(build_cell(&format!("i127({I127_MAX})")).is_ok());
+        assert!(build_cell(&format!("i127({I127_MIN})")).is_ok());
+        assert!(build_cell("i127(85070591730234615865843651857942052864)").is_err());
+        assert!(build_cell("i127(-85070591730234615865843651857942052865)").is_err());
+
+        let cell = build_cell(&format!("i128(-{I128_MAX})")).unwrap();
+        assert_eq!(cell.bit_length(), 128);
+        assert_eq!(cell.data()[0], 0x80);
+
+        let cell = build_cell("i256(-1)").unwrap();
+        assert_eq!(cell.data()[..32], [0xff; 32]);
+    }
+}
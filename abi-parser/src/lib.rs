@@ -3,6 +3,8 @@ use std::str::FromStr;
 use pest::iterators::Pair;
 use pest::Parser;
 
+pub mod cell;
+
 #[derive(pest_derive::Parser)]
 #[grammar = "abi.pest"]
 struct Grammar;
@@ -196,6 +198,8 @@ pub enum ParserError {
     InvalidFixedBytesLength,
     #[error("invalid fixed array length")]
     InvalidFixedArrayLength,
+    #[error("invalid cell: {0}")]
+    InvalidCell(String),
 }
 
 #[cfg(test)]
@@ -0,0 +1,348 @@
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result};
+use shared::{HandleError, ObjectBuilder};
+use ton_block::{AnycastInfo, Deserializable, MsgAddrStd, MsgAddress, MsgAddressInt};
+use ton_types::SliceData;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+const CRC16: crc::Crc<u16> = crc::Crc::<u16>::new(&crc::CRC_16_XMODEM);
+
+const FRIENDLY_BOUNCEABLE_TAG: u8 = 0x11;
+const FRIENDLY_NON_BOUNCEABLE_TAG: u8 = 0x51;
+const FRIENDLY_TESTNET_FLAG: u8 = 0x80;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct FriendlyFlags {
+    pub bounceable: bool,
+    pub testnet: bool,
+    pub url_safe: bool,
+}
+
+impl Default for FriendlyFlags {
+    fn default() -> Self {
+        Self {
+            bounceable: true,
+            testnet: false,
+            url_safe: true,
+        }
+    }
+}
+
+/// Flags of an unpacked user-friendly address.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct UnpackedFlags {
+    pub bounceable: bool,
+    pub testnet: bool,
+    /// `None` when the address has no characters specific to either base64 alphabet.
+    pub url_safe: Option<bool>,
+}
+
+/// Packs a standard address into the 48-character user-friendly form.
+pub fn pack_friendly(address: &MsgAddressInt, flags: FriendlyFlags) -> Result<String> {
+    let (workchain_id, hash) = match address {
+        MsgAddressInt::AddrStd(MsgAddrStd {
+            anycast: Some(_), ..
+        }) => return Err(anyhow!("Anycast addresses have no user-friendly form")),
+        MsgAddressInt::AddrStd(MsgAddrStd {
+            workchain_id,
+            address,
+            ..
+        }) if address.remaining_bits() == 256 => (*workchain_id, address.get_bytestring(0)),
+        _ => return Err(anyhow!("Only standard addresses have a user-friendly form")),
+    };
+
+    let mut tag = if flags.bounceable {
+        FRIENDLY_BOUNCEABLE_TAG
+    } else {
+        FRIENDLY_NON_BOUNCEABLE_TAG
+    };
+    if flags.testnet {
+        tag |= FRIENDLY_TESTNET_FLAG;
+    }
+
+    let mut bytes = Vec::with_capacity(36);
+    bytes.push(tag);
+    bytes.push(workchain_id as u8);
+    bytes.extend_from_slice(&hash);
+    bytes.extend_from_slice(&CRC16.checksum(&bytes).to_be_bytes());
+
+    Ok(if flags.url_safe {
+        base64::encode_config(bytes, base64::URL_SAFE)
+    } else {
+        base64::encode(bytes)
+    })
+}
+
+/// Unpacks a user-friendly address, verifying its checksum.
+pub fn unpack_friendly(address: &str) -> Result<(MsgAddressInt, UnpackedFlags)> {
+    let address = address.trim();
+    let url_safe = if address.contains(|c| c == '-' || c == '_') {
+        Some(true)
+    } else if address.contains(|c| c == '+' || c == '/') {
+        Some(false)
+    } else {
+        None
+    };
+    let bytes = base64::decode_config(address, base64::URL_SAFE)
+        .or_else(|_| base64::decode(address))
+        .map_err(|_| anyhow!("Invalid user-friendly address encoding"))?;
+    if bytes.len() != 36 {
+        return Err(anyhow!("Invalid user-friendly address length"));
+    }
+
+    let crc = u16::from_be_bytes([bytes[34], bytes[35]]);
+    if CRC16.checksum(&bytes[..34]) != crc {
+        return Err(anyhow!("Invalid user-friendly address checksum"));
+    }
+
+    let tag = bytes[0];
+    let bounceable = match tag & !FRIENDLY_TESTNET_FLAG {
+        FRIENDLY_BOUNCEABLE_TAG => true,
+        FRIENDLY_NON_BOUNCEABLE_TAG => false,
+        _ => return Err(anyhow!("Invalid user-friendly address tag")),
+    };
+
+    let address = MsgAddressInt::with_standart(
+        None,
+        bytes[1] as i8,
+        SliceData::from_raw(bytes[2..34].to_vec(), 256),
+    )?;
+    Ok((
+        address,
+        UnpackedFlags {
+            bounceable,
+            testnet: tag & FRIENDLY_TESTNET_FLAG != 0,
+            url_safe,
+        },
+    ))
+}
+
+/// Parses an address either in the raw `wc:hex` or in the user-friendly form.
+pub fn parse_address(address: &str) -> Result<MsgAddressInt> {
+    let address = address.trim();
+    if address.contains(':') {
+        MsgAddressInt::from_str(address)
+    } else {
+        unpack_friendly(address).map(|(address, _)| address)
+    }
+}
+
+#[wasm_bindgen(typescript_custom_section)]
+const ADDRESS: &str = r#"
+export type FriendlyAddressFlags = {
+    bounceable?: boolean,
+    testnet?: boolean,
+    urlSafe?: boolean,
+};
+
+export type UnpackedAddress = {
+    address: string,
+    bounceable: boolean,
+    testnet: boolean,
+    urlSafe: boolean | undefined,
+};
+
+export type AnycastInfo = {
+    depth: number,
+    rewritePfx: string,
+};
+
+export type AddressInfo =
+    | { type: 'none' }
+    | { type: 'external', bits: number, address: string }
+    | { type: 'std', workchain: number, address: string, anycast: AnycastInfo | undefined, raw: string }
+    | { type: 'var', workchain: number, bits: number, address: string, anycast: AnycastInfo | undefined };
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "FriendlyAddressFlags | undefined")]
+    pub type OptionalFriendlyAddressFlags;
+
+    #[wasm_bindgen(typescript_type = "UnpackedAddress")]
+    pub type UnpackedAddress;
+
+    #[wasm_bindgen(typescript_type = "AddressInfo")]
+    pub type AddressInfo;
+}
+
+/// Converts a raw or user-friendly address into the user-friendly form.
+#[wasm_bindgen(js_name = "packAddress")]
+pub fn pack_address(address: &str, flags: OptionalFriendlyAddressFlags) -> Result<String, JsValue> {
+    let address = parse_address(address).handle_error()?;
+
+    let mut result = FriendlyFlags::default();
+    if !flags.is_undefined() && !flags.is_null() {
+        let get = |key: &str| {
+            js_sys::Reflect::get(&flags, &JsValue::from_str(key)).unwrap_or(JsValue::UNDEFINED)
+        };
+        if let Some(bounceable) = get("bounceable").as_bool() {
+            result.bounceable = bounceable;
+        }
+        if let Some(testnet) = get("testnet").as_bool() {
+            result.testnet = testnet;
+        }
+        if let Some(url_safe) = get("urlSafe").as_bool() {
+            result.url_safe = url_safe;
+        }
+    }
+
+    pack_friendly(&address, result).handle_error()
+}
+
+/// Converts a user-friendly address into the raw `wc:hex` form along with its flags.
+///
+/// `urlSafe` is undefined when the address is valid in both base64 alphabets.
+#[wasm_bindgen(js_name = "unpackAddress")]
+pub fn unpack_address(address: &str) -> Result<UnpackedAddress, JsValue> {
+    let (address, flags) = unpack_friendly(address).handle_error()?;
+    Ok(ObjectBuilder::new()
+        .set("address", address.to_string())
+        .set("bounceable", flags.bounceable)
+        .set("testnet", flags.testnet)
+        .set("urlSafe", flags.url_safe)
+        .build()
+        .unchecked_into())
+}
+
+/// Computes the raw address of a contract deployed with the specified StateInit.
+#[wasm_bindgen(js_name = "computeAddress")]
+pub fn compute_address(state_init_boc: &str, workchain: i8) -> Result<String, JsValue> {
+    let cell = crate::boc::parse_cell(state_init_boc).handle_error()?;
+    ton_block::StateInit::construct_from_cell(cell.clone()).handle_error()?;
+    Ok(format!(
+        "{}:{}",
+        workchain,
+        cell.repr_hash().to_hex_string()
+    ))
+}
+
+/// Parses a raw or user-friendly address, or a BOC with a serialized `MsgAddress`
+/// (including anycast, var and external addresses).
+#[wasm_bindgen(js_name = "parseAddressInfo")]
+pub fn parse_address_info(address: &str) -> Result<AddressInfo, JsValue> {
+    let address = match parse_address(address) {
+        Ok(MsgAddressInt::AddrStd(address)) => MsgAddress::AddrStd(address),
+        Ok(MsgAddressInt::AddrVar(address)) => MsgAddress::AddrVar(address),
+        Err(_) => crate::boc::parse_cell(address)
+            .and_then(MsgAddress::construct_from_cell)
+            .map_err(|_| {
+                anyhow!("Invalid address: expected a raw or user-friendly address or a BOC")
+            })
+            .handle_error()?,
+    };
+
+    let info = match &address {
+        MsgAddress::AddrNone => ObjectBuilder::new().set("type", "none"),
+        MsgAddress::AddrExt(address) => ObjectBuilder::new()
+            .set("type", "external")
+            .set("bits", address.len.as_u32())
+            .set("address", address.external_address.to_hex_string()),
+        MsgAddress::AddrStd(std) => ObjectBuilder::new()
+            .set("type", "std")
+            .set("workchain", std.workchain_id)
+            .set("address", std.address.to_hex_string())
+            .set("anycast", std.anycast.as_ref().map(make_anycast_info))
+            .set("raw", address.to_string()),
+        MsgAddress::AddrVar(var) => ObjectBuilder::new()
+            .set("type", "var")
+            .set("workchain", var.workchain_id)
+            .set("bits", var.addr_len.as_u32())
+            .set("address", var.address.to_hex_string())
+            .set("anycast", var.anycast.as_ref().map(make_anycast_info)),
+    };
+
+    Ok(info.build().unchecked_into())
+}
+
+fn make_anycast_info(anycast: &AnycastInfo) -> JsValue {
+    ObjectBuilder::new()
+        .set("depth", anycast.depth.as_u32())
+        .set("rewritePfx", anycast.rewrite_pfx.to_hex_string())
+        .build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RAW: &str = "0:83dfd552e63729b472fcbcc8c45ebcc6691702558b68ec7527e1ba403a0f31a8";
+
+    #[test]
+    fn friendly_flags() {
+        let address = MsgAddressInt::from_str(RAW).unwrap();
+        for (friendly, bounceable, testnet) in [
+            (
+                "EQCD39VS5jcptHL8vMjEXrzGaRcCVYto7HUn4bpAOg8xqB2N",
+                true,
+                false,
+            ),
+            (
+                "UQCD39VS5jcptHL8vMjEXrzGaRcCVYto7HUn4bpAOg8xqEBI",
+                false,
+                false,
+            ),
+            (
+                "kQCD39VS5jcptHL8vMjEXrzGaRcCVYto7HUn4bpAOg8xqKYH",
+                true,
+                true,
+            ),
+        ] {
+            let flags = FriendlyFlags {
+                bounceable,
+                testnet,
+                url_safe: true,
+            };
+            assert_eq!(pack_friendly(&address, flags).unwrap(), friendly);
+
+            let (unpacked, flags) = unpack_friendly(friendly).unwrap();
+            assert_eq!(unpacked, address);
+            assert_eq!(
+                flags,
+                UnpackedFlags {
+                    bounceable,
+                    testnet,
+                    url_safe: None,
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn friendly_alphabets() {
+        let address = MsgAddressInt::from_str(&format!("-1:{}", "ff".repeat(32))).unwrap();
+        let url_safe = "Ef___________________________________________7Sg";
+        let standard = "Ef///////////////////////////////////////////7Sg";
+
+        let flags = FriendlyFlags::default();
+        assert_eq!(pack_friendly(&address, flags).unwrap(), url_safe);
+        let flags = FriendlyFlags {
+            url_safe: false,
+            ..Default::default()
+        };
+        assert_eq!(pack_friendly(&address, flags).unwrap(), standard);
+
+        let (unpacked, flags) = unpack_friendly(url_safe).unwrap();
+        assert_eq!(unpacked, address);
+        assert_eq!(flags.url_safe, Some(true));
+        let (unpacked, flags) = unpack_friendly(standard).unwrap();
+        assert_eq!(unpacked, address);
+        assert_eq!(flags.url_safe, Some(false));
+    }
+
+    #[test]
+    fn invalid_friendly() {
+        assert!(unpack_friendly("EQCD39VS5jcptHL8vMjEXrzGaRcCVYto7HUn4bpAOg8xqB2M").is_err());
+        assert!(unpack_friendly("EQCD39VS5jcptHL8vMjEXrzGaRcCVYto7HUn4bpAOg8xqB2").is_err());
+        assert!(unpack_friendly("Ef_/_________________________________________7Sg").is_err());
+
+        let address = MsgAddressInt::from_str(RAW).unwrap();
+        assert_eq!(
+            parse_address("EQCD39VS5jcptHL8vMjEXrzGaRcCVYto7HUn4bpAOg8xqB2N").unwrap(),
+            address
+        );
+        assert_eq!(parse_address(RAW).unwrap(), address);
+    }
+}
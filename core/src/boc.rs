@@ -0,0 +1,547 @@
+use std::collections::{HashMap, HashSet};
+use std::convert::TryInto;
+
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+use shared::*;
+use ton_types::{Cell, CellType, UInt256};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+pub const BOC_GENERIC_TAG: u32 = 0xb5ee9c72;
+pub const BOC_INDEXED_TAG: u32 = 0x68ff65f3;
+pub const BOC_INDEXED_CRC32_TAG: u32 = 0xacc3a728;
+
+const CRC32C: crc::Crc<u32> = crc::Crc::<u32>::new(&crc::CRC_32_ISCSI);
+
+/// Decodes BOC bytes from a base64, base64url or hex string.
+pub fn decode_boc(input: &str) -> Result<Vec<u8>> {
+    let input = input.trim();
+
+    let is_hex = input.len() % 2 == 0 && input.chars().all(|c| c.is_ascii_hexdigit());
+    if is_hex {
+        if let Ok(bytes) = hex::decode(input) {
+            if has_boc_magic(&bytes) {
+                return Ok(bytes);
+            }
+        }
+    }
+
+    base64::decode(input)
+        .or_else(|_| base64::decode_config(input, base64::STANDARD_NO_PAD))
+        .or_else(|_| base64::decode_config(input, base64::URL_SAFE))
+        .or_else(|_| base64::decode_config(input, base64::URL_SAFE_NO_PAD))
+        .or_else(|e| {
+            if is_hex {
+                hex::decode(input).map_err(|_| e)
+            } else {
+                Err(e)
+            }
+        })
+        .map_err(|_| anyhow!("Invalid BOC: expected a base64, base64url or hex string"))
+}
+
+/// Decodes a BOC string, verifies its header and returns all of its roots.
+pub fn parse_boc(input: &str) -> Result<(BocHeader, Vec<Cell>)> {
+    let bytes = decode_boc(input)?;
+    let header = BocHeader::parse(&bytes)?;
+    let roots = ton_types::deserialize_cells_tree(&mut bytes.as_slice())?;
+    Ok((header, roots))
+}
+
+/// Decodes a BOC string and returns its first root.
+pub fn parse_cell(input: &str) -> Result<Cell> {
+    let (_, roots) = parse_boc(input)?;
+    roots
+        .into_iter()
+        .next()
+        .ok_or_else(|| anyhow!("BOC has no roots"))
+}
+
+#[wasm_bindgen(typescript_custom_section)]
+const BOC_WRITER_OPTIONS: &str = r#"
+export type BocWriterOptions = {
+    index?: boolean,
+    crc32c?: boolean,
+    cacheBits?: boolean,
+};
+
+export type ReserializedBoc = {
+    boc: string,
+    size: number,
+    hashesMatch: boolean,
+};
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "BocWriterOptions | undefined")]
+    pub type OptionalBocWriterOptions;
+
+    #[wasm_bindgen(typescript_type = "ReserializedBoc")]
+    pub type ReserializedBoc;
+}
+
+/// Serializes all roots of the BOC again with the specified options.
+#[wasm_bindgen(js_name = "reserializeBoc")]
+pub fn reserialize_boc(
+    boc: &str,
+    options: OptionalBocWriterOptions,
+) -> Result<ReserializedBoc, JsValue> {
+    let options = BocWriterOptions::from_js(&options).handle_error()?;
+    let (_, roots) = parse_boc(boc).handle_error()?;
+    let bytes = write_boc(&roots, options).handle_error()?;
+    let hashes_match = check_root_hashes(&roots, &bytes);
+
+    Ok(ObjectBuilder::new()
+        .set("size", bytes.len() as u32)
+        .set("boc", base64::encode(bytes))
+        .set("hashesMatch", hashes_match)
+        .build()
+        .unchecked_into())
+}
+
+/// Reports the BOC size for each combination of the serialization options,
+/// along with the size produced by `serialize_toc`.
+#[wasm_bindgen(js_name = "compareBocSizes")]
+pub fn compare_boc_sizes(boc: &str) -> Result<String, JsValue> {
+    let (header, roots) = parse_boc(boc).handle_error()?;
+
+    let mut variants = Vec::new();
+    for index in [false, true] {
+        for crc32c in [false, true] {
+            for cache_bits in [false, true] {
+                if cache_bits && !index {
+                    continue;
+                }
+
+                let options = BocWriterOptions {
+                    index,
+                    crc32c,
+                    cache_bits,
+                };
+                let bytes = write_boc(&roots, options).handle_error()?;
+                variants.push(serde_json::json!({
+                    "index": index,
+                    "crc32c": crc32c,
+                    "cache_bits": cache_bits,
+                    "size": bytes.len(),
+                    "hashes_match": check_root_hashes(&roots, &bytes),
+                }));
+            }
+        }
+    }
+
+    let serialize_toc_size = match roots.as_slice() {
+        [root] => Some(ton_types::serialize_toc(root).handle_error()?.len()),
+        _ => None,
+    };
+
+    Ok(serde_json::json!({
+        "original": header.to_json(),
+        "serialize_toc_size": serialize_toc_size,
+        "variants": variants,
+    })
+    .to_string())
+}
+
+#[derive(Debug, Copy, Clone, Default)]
+pub struct BocWriterOptions {
+    pub index: bool,
+    pub crc32c: bool,
+    /// Marks cells referenced more than once in the index. Requires `index`.
+    pub cache_bits: bool,
+}
+
+impl BocWriterOptions {
+    fn from_js(options: &JsValue) -> Result<Self> {
+        let mut result = Self::default();
+        if options.is_undefined() || options.is_null() {
+            return Ok(result);
+        }
+
+        let get = |key: &str| {
+            js_sys::Reflect::get(options, &JsValue::from_str(key)).unwrap_or(JsValue::UNDEFINED)
+        };
+
+        result.index = get("index").as_bool().unwrap_or_default();
+        result.crc32c = get("crc32c").as_bool().unwrap_or_default();
+        result.cache_bits = get("cacheBits").as_bool().unwrap_or_default();
+        if result.cache_bits && !result.index {
+            return Err(anyhow!("Cache bits require the index"));
+        }
+
+        Ok(result)
+    }
+}
+
+/// Serializes the roots into a generic BOC with the specified options.
+pub fn write_boc(roots: &[Cell], options: BocWriterOptions) -> Result<Vec<u8>> {
+    if roots.is_empty() {
+        return Err(anyhow!("BOC has no roots"));
+    }
+    if options.cache_bits && !options.index {
+        return Err(anyhow!("Cache bits require the index"));
+    }
+
+    // Order cells so that each cell goes before its references
+    let mut visited = HashSet::new();
+    let mut usages = HashMap::new();
+    let mut order = Vec::new();
+    for root in roots {
+        visit_cell(root, &mut visited, &mut usages, &mut order)?;
+    }
+    order.reverse();
+    let indices = order
+        .iter()
+        .enumerate()
+        .map(|(index, cell)| (cell.repr_hash(), index))
+        .collect::<HashMap<_, _>>();
+
+    let size_bytes = min_bytes(order.len() as u64);
+    let mut cells_data = Vec::new();
+    let mut offsets = Vec::with_capacity(order.len());
+    for cell in &order {
+        let bits = cell.bit_length();
+        let level_mask = cell.level_mask().mask();
+        let is_exotic = cell.cell_type() != CellType::Ordinary;
+
+        cells_data
+            .push(cell.references_count() as u8 + if is_exotic { 8 } else { 0 } + level_mask * 32);
+        cells_data.push(((bits / 8) + (bits + 7) / 8) as u8);
+
+        let data = cell.data();
+        let len = (bits + 7) / 8;
+        cells_data.extend_from_slice(&data[..len]);
+        let rem = bits % 8;
+        if rem != 0 {
+            let last = cells_data.len() - 1;
+            cells_data[last] = (cells_data[last] & !(0xff >> rem)) | (0x80 >> rem);
+        }
+
+        for i in 0..cell.references_count() {
+            let child = cell.reference(i)?;
+            write_be(
+                &mut cells_data,
+                indices[&child.repr_hash()] as u64,
+                size_bytes,
+            );
+        }
+        offsets.push(cells_data.len() as u64);
+    }
+    let offset_bytes = if options.cache_bits {
+        min_bytes(cells_data.len() as u64 * 2 + 1)
+    } else {
+        min_bytes(cells_data.len() as u64)
+    };
+
+    let mut result = Vec::with_capacity(cells_data.len() + 32);
+    result.extend_from_slice(&BOC_GENERIC_TAG.to_be_bytes());
+    result.push(
+        (options.index as u8) << 7
+            | (options.crc32c as u8) << 6
+            | (options.cache_bits as u8) << 5
+            | size_bytes,
+    );
+    result.push(offset_bytes);
+    write_be(&mut result, order.len() as u64, size_bytes);
+    write_be(&mut result, roots.len() as u64, size_bytes);
+    write_be(&mut result, 0, size_bytes);
+    write_be(&mut result, cells_data.len() as u64, offset_bytes);
+    for root in roots {
+        write_be(&mut result, indices[&root.repr_hash()] as u64, size_bytes);
+    }
+    if options.index {
+        for (cell, offset) in order.iter().zip(offsets) {
+            let offset = if options.cache_bits {
+                offset * 2 + (usages[&cell.repr_hash()] > 1) as u64
+            } else {
+                offset
+            };
+            write_be(&mut result, offset, offset_bytes);
+        }
+    }
+    result.extend_from_slice(&cells_data);
+    if options.crc32c {
+        let crc = CRC32C.checksum(&result);
+        result.extend_from_slice(&crc.to_le_bytes());
+    }
+
+    Ok(result)
+}
+
+fn visit_cell(
+    cell: &Cell,
+    visited: &mut HashSet<UInt256>,
+    usages: &mut HashMap<UInt256, usize>,
+    order: &mut Vec<Cell>,
+) -> Result<()> {
+    let hash = cell.repr_hash();
+    *usages.entry(hash.clone()).or_default() += 1;
+    if !visited.insert(hash) {
+        return Ok(());
+    }
+
+    for i in (0..cell.references_count()).rev() {
+        visit_cell(&cell.reference(i)?, visited, usages, order)?;
+    }
+    order.push(cell.clone());
+    Ok(())
+}
+
+fn check_root_hashes(roots: &[Cell], bytes: &[u8]) -> bool {
+    match ton_types::deserialize_cells_tree(&mut &bytes[..]) {
+        Ok(parsed) => {
+            parsed.len() == roots.len()
+                && parsed
+                    .iter()
+                    .zip(roots)
+                    .all(|(a, b)| a.repr_hash() == b.repr_hash())
+        }
+        Err(_) => false,
+    }
+}
+
+fn min_bytes(value: u64) -> u8 {
+    ((64 - value.leading_zeros() + 7) / 8).max(1) as u8
+}
+
+fn write_be(result: &mut Vec<u8>, value: u64, bytes: u8) {
+    result.extend_from_slice(&value.to_be_bytes()[8 - bytes as usize..]);
+}
+
+fn has_boc_magic(bytes: &[u8]) -> bool {
+    matches!(
+        bytes
+            .get(0..4)
+            .map(|magic| u32::from_be_bytes(magic.try_into().unwrap())),
+        Some(BOC_GENERIC_TAG | BOC_INDEXED_TAG | BOC_INDEXED_CRC32_TAG)
+    )
+}
+
+#[derive(Debug, Clone)]
+pub struct BocHeader {
+    pub magic: u32,
+    pub has_index: bool,
+    pub has_crc32c: bool,
+    pub has_cache_bits: bool,
+    pub flags: u8,
+    pub size_bytes: u8,
+    pub offset_bytes: u8,
+    pub cell_count: usize,
+    pub root_count: usize,
+    pub absent_count: usize,
+    pub total_cells_size: usize,
+    pub root_indices: Vec<usize>,
+    pub crc32c: Option<u32>,
+    pub total_size: usize,
+}
+
+impl BocHeader {
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        let mut reader = Reader { data, offset: 0 };
+
+        let magic = reader.read_be(4)? as u32;
+        let (has_index, has_crc32c, has_cache_bits, flags, size_bytes) = match magic {
+            BOC_GENERIC_TAG => {
+                let header = reader.read_be(1)? as u8;
+                (
+                    header & 0x80 != 0,
+                    header & 0x40 != 0,
+                    header & 0x20 != 0,
+                    (header >> 3) & 0b11,
+                    header & 0b111,
+                )
+            }
+            BOC_INDEXED_TAG | BOC_INDEXED_CRC32_TAG => {
+                let size = reader.read_be(1)? as u8;
+                (true, magic == BOC_INDEXED_CRC32_TAG, false, 0, size)
+            }
+            _ => return Err(anyhow!("Unknown BOC magic: {magic:08x}")),
+        };
+        if !(1..=4).contains(&size_bytes) {
+            return Err(anyhow!("Invalid BOC ref size: {size_bytes}"));
+        }
+
+        let offset_bytes = reader.read_be(1)? as u8;
+        if !(1..=8).contains(&offset_bytes) {
+            return Err(anyhow!("Invalid BOC offset size: {offset_bytes}"));
+        }
+
+        let cell_count = reader.read_be(size_bytes)? as usize;
+        let root_count = reader.read_be(size_bytes)? as usize;
+        let absent_count = reader.read_be(size_bytes)? as usize;
+        let total_cells_size = reader.read_be(offset_bytes)? as usize;
+        if root_count == 0 || root_count > cell_count || absent_count > cell_count {
+            return Err(anyhow!("Invalid BOC cell counts"));
+        }
+
+        let root_indices = if magic == BOC_GENERIC_TAG {
+            (0..root_count)
+                .map(|_| reader.read_be(size_bytes).map(|index| index as usize))
+                .collect::<Result<Vec<_>>>()?
+        } else {
+            (0..root_count).collect()
+        };
+
+        if has_index {
+            reader.skip(cell_count * offset_bytes as usize)?;
+        }
+        reader.skip(total_cells_size)?;
+
+        let crc32c = if has_crc32c {
+            let expected = reader.read_le_u32()?;
+            let computed = CRC32C.checksum(&data[..reader.offset - 4]);
+            if expected != computed {
+                return Err(anyhow!(
+                    "BOC CRC32C mismatch: expected {expected:08x}, computed {computed:08x}"
+                ));
+            }
+            Some(expected)
+        } else {
+            None
+        };
+
+        Ok(Self {
+            magic,
+            has_index,
+            has_crc32c,
+            has_cache_bits,
+            flags,
+            size_bytes,
+            offset_bytes,
+            cell_count,
+            root_count,
+            absent_count,
+            total_cells_size,
+            root_indices,
+            crc32c,
+            total_size: reader.offset,
+        })
+    }
+
+    pub fn to_json(&self) -> Value {
+        serde_json::json!({
+            "magic": format!("{:08x}", self.magic),
+            "has_index": self.has_index,
+            "has_crc32c": self.has_crc32c,
+            "has_cache_bits": self.has_cache_bits,
+            "flags": self.flags,
+            "size_bytes": self.size_bytes,
+            "offset_bytes": self.offset_bytes,
+            "cell_count": self.cell_count,
+            "root_count": self.root_count,
+            "absent_count": self.absent_count,
+            "total_cells_size": self.total_cells_size,
+            "root_indices": self.root_indices,
+            "crc32c": self.crc32c.map(|crc| format!("{crc:08x}")),
+            "total_size": self.total_size,
+        })
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl Reader<'_> {
+    fn take(&mut self, len: usize) -> Result<&[u8]> {
+        let bytes = self
+            .offset
+            .checked_add(len)
+            .and_then(|end| self.data.get(self.offset..end))
+            .ok_or_else(|| anyhow!("Unexpected end of BOC"))?;
+        self.offset += len;
+        Ok(bytes)
+    }
+
+    fn skip(&mut self, len: usize) -> Result<()> {
+        self.take(len).map(|_| ())
+    }
+
+    fn read_be(&mut self, len: u8) -> Result<u64> {
+        Ok(self
+            .take(len as usize)?
+            .iter()
+            .fold(0, |value, byte| (value << 8) | *byte as u64))
+    }
+
+    fn read_le_u32(&mut self) -> Result<u32> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes(bytes.try_into().unwrap()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ton_types::BuilderData;
+
+    use super::*;
+
+    fn make_cell(data: &[u8], bits: usize, references: &[Cell]) -> Cell {
+        let mut builder = BuilderData::new();
+        builder.append_raw(data, bits).unwrap();
+        for reference in references {
+            builder.checked_append_reference(reference.clone()).unwrap();
+        }
+        builder.into_cell().unwrap()
+    }
+
+    #[test]
+    fn write_boc_matches_serialize_toc() {
+        let leaf = make_cell(&[0xab, 0xc0], 10, &[]);
+        let middle = make_cell(&[0x12, 0x34, 0x56], 24, &[leaf]);
+        let root = make_cell(&[0xff], 3, &[middle]);
+
+        let written = write_boc(std::slice::from_ref(&root), Default::default()).unwrap();
+        assert_eq!(written, ton_types::serialize_toc(&root).unwrap());
+
+        let shared = make_cell(&[0x55], 8, &[]);
+        let left = make_cell(&[0x01], 8, &[shared.clone()]);
+        let right = make_cell(&[0x02], 8, &[shared.clone()]);
+        let root = make_cell(&[], 0, &[left, right, shared]);
+
+        let expected = ton_types::serialize_toc(&root).unwrap();
+        for options in [
+            BocWriterOptions::default(),
+            BocWriterOptions {
+                index: true,
+                crc32c: true,
+                cache_bits: true,
+            },
+        ] {
+            let written = write_boc(std::slice::from_ref(&root), options).unwrap();
+            assert!(check_root_hashes(std::slice::from_ref(&root), &written));
+
+            let header = BocHeader::parse(&written).unwrap();
+            assert_eq!(header.cell_count, 4);
+            assert_eq!(header.has_index, options.index);
+            assert_eq!(header.has_crc32c, options.crc32c);
+            assert_eq!(header.total_size, written.len());
+            if !options.index && !options.crc32c {
+                assert_eq!(written.len(), expected.len());
+            }
+        }
+    }
+
+    #[test]
+    fn header_rejects_bad_crc() {
+        let root = make_cell(&[0xde, 0xad, 0xbe, 0xef], 32, &[]);
+        let options = BocWriterOptions {
+            crc32c: true,
+            ..Default::default()
+        };
+        let mut written = write_boc(&[root], options).unwrap();
+        assert!(BocHeader::parse(&written).unwrap().crc32c.is_some());
+
+        let last = written.len() - 1;
+        written[last] ^= 0x01;
+        let error = BocHeader::parse(&written).unwrap_err();
+        assert!(error.to_string().contains("CRC32C mismatch"));
+
+        written[last] ^= 0x01;
+        written[last - 4] ^= 0x01;
+        assert!(BocHeader::parse(&written).is_err());
+    }
+}
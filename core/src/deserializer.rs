@@ -1,11 +1,13 @@
+use std::convert::TryFrom;
 use std::str::FromStr;
 
 use anyhow::{anyhow, Result};
 use serde_json::{Map, Value};
 use ton_block::*;
 use ton_block_json::*;
-use ton_types::{deserialize_tree_of_cells, serialize_toc, SliceData};
+use ton_types::{serialize_toc, Cell, SliceData, UInt256};
 use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
 
 use shared::*;
 
@@ -18,7 +20,8 @@ export type StructureType =
     | 'block'
     | 'message'
     | 'transaction'
-    | 'account';
+    | 'account'
+    | 'stateInit';
 "#;
 
 #[wasm_bindgen]
@@ -27,20 +30,199 @@ extern "C" {
     pub type OptionalStructureType;
 }
 
+#[wasm_bindgen(typescript_custom_section)]
+const DESERIALIZATION_OPTIONS: &str = r#"
+export type DeserializationOptions = {
+    mode?: 'standard' | 'qServer' | 'debug',
+    amounts?: 'nano' | 'tokens',
+    addresses?: 'raw' | 'friendly',
+    bounceable?: boolean,
+    testnet?: boolean,
+    lt?: 'decimal' | 'hex',
+    abis?: string[],
+};
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "DeserializationOptions | undefined")]
+    pub type OptionalDeserializationOptions;
+}
+
 #[wasm_bindgen]
-pub fn deserialize(boc: &str, structure_type: OptionalStructureType) -> Result<String, JsValue> {
-    let boc = base64::decode(boc.trim()).handle_error()?;
+pub fn deserialize(
+    boc: &str,
+    structure_type: OptionalStructureType,
+    options: OptionalDeserializationOptions,
+) -> Result<String, JsValue> {
+    let options = DeserializationOptions::from_js(&options).handle_error()?;
+    let cell = crate::boc::parse_cell(boc).handle_error()?;
     let ty = match structure_type.as_string() {
         Some(structure_type) => StructureType::from_str(structure_type.trim()).handle_error()?,
-        None => try_detect_type(&boc).handle_error()?,
+        None => try_detect_type(&cell).handle_error()?,
     };
-    Ok(ty.deserialize(&boc).handle_error()?.to_string())
+    Ok(ty.deserialize(cell, &options).handle_error()?.to_string())
+}
+
+/// Deserializes every root of the BOC and returns them along with the parsed BOC header.
+///
+/// The structure type is detected for each root separately unless it is specified explicitly.
+#[wasm_bindgen(js_name = "deserializeRoots")]
+pub fn deserialize_roots(
+    boc: &str,
+    structure_type: OptionalStructureType,
+    options: OptionalDeserializationOptions,
+) -> Result<String, JsValue> {
+    let options = DeserializationOptions::from_js(&options).handle_error()?;
+    let (header, roots) = crate::boc::parse_boc(boc).handle_error()?;
+    let structure_type = match structure_type.as_string() {
+        Some(structure_type) => {
+            Some(StructureType::from_str(structure_type.trim()).handle_error()?)
+        }
+        None => None,
+    };
+
+    let roots = roots
+        .into_iter()
+        .zip(header.root_indices.iter())
+        .map(|(cell, index)| {
+            let mut map = Map::new();
+            map.insert("index".to_string(), (*index).into());
+            map.insert("hash".to_string(), cell.repr_hash().to_hex_string().into());
+
+            let ty = match structure_type {
+                Some(ty) => Ok(ty),
+                None => try_detect_type(&cell),
+            };
+            match ty.and_then(|ty| Ok((ty, ty.deserialize(cell, &options)?))) {
+                Ok((ty, value)) => {
+                    map.insert("type".to_string(), ty.as_str().into());
+                    map.insert("value".to_string(), value);
+                }
+                Err(e) => {
+                    map.insert("type".to_string(), Value::Null);
+                    map.insert("error".to_string(), e.to_string().into());
+                }
+            }
+            Value::from(map)
+        })
+        .collect::<Vec<_>>();
+
+    Ok(serde_json::json!({
+        "header": header.to_json(),
+        "roots": roots,
+    })
+    .to_string())
 }
 
-fn try_detect_type(mut boc: &[u8]) -> Result<StructureType> {
-    let cell = deserialize_tree_of_cells(&mut boc)?;
+#[wasm_bindgen(typescript_custom_section)]
+const TRANSACTION_FEES: &str = r#"
+export type TransactionFees = {
+    total: string,
+    totalWithForwarding: string,
+    storage: string,
+    storageDue: string,
+    credit: string,
+    creditedValue: string,
+    compute: string,
+    gasUsed: string,
+    gasLimit: string,
+    gasPrice: string | undefined,
+    action: string,
+    forward: string,
+    bounce: string,
+    bounceForward: string,
+    import: string,
+    outMessages: {
+        hash: string,
+        value: string,
+        fwdFee: string,
+    }[],
+};
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "TransactionFees")]
+    pub type TransactionFeesJs;
+}
+
+/// Breaks the transaction fees down by phases.
+///
+/// The gas price is read from the config params BOC when it is specified,
+/// the workchain of the account is `0` by default.
+#[wasm_bindgen(js_name = "getTransactionFees")]
+pub fn get_transaction_fees(
+    tx: &str,
+    config: Option<String>,
+    workchain: Option<i32>,
+) -> Result<TransactionFeesJs, JsValue> {
+    let cell = crate::boc::parse_cell(tx).handle_error()?;
+    let tx = Transaction::construct_from_cell(cell).handle_error()?;
+    let mut fees = shared::fees::TransactionFees::compute(&tx).handle_error()?;
+    if let Some(config) = config {
+        let config = ton_block::ConfigParams::construct_from_cell(
+            crate::boc::parse_cell(&config).handle_error()?,
+        )
+        .handle_error()?;
+        let is_masterchain = workchain.unwrap_or_default() == ton_block::MASTERCHAIN_ID;
+        fees.gas_price =
+            Some(shared::fees::TransactionFees::gas_price(&config, is_masterchain).handle_error()?);
+    }
+    Ok(fees.build().unchecked_into())
+}
+
+/// Encodes an edited `deserialize` output back into a BOC.
+///
+/// The `boc` field of the input is used as a base structure. Only the editable
+/// fields whose values differ from the base are applied, so the unchanged output
+/// can be passed as is. Amounts and logical times of the edited fields are accepted
+/// as numbers, `0x`-prefixed hex strings or strings in the format of the output:
+/// length-prefixed hex in the `qServer` mode and decimal otherwise.
+///
+/// Editable fields:
+/// - `message`: `src`, `dst`, `value`, `bounce`, `bounced`, `ihr_disabled`, `ihr_fee`,
+///   `fwd_fee`, `import_fee`, `created_lt`, `created_at`, `body`, `init` (StateInit BOC);
+/// - `transaction`: `account_addr`, `lt`, `prev_trans_lt`, `prev_trans_hash`, `now`,
+///   `orig_status`, `end_status`, `total_fees`, `in_msg_boc`, `out_msgs_boc`;
+/// - `account`: `id`, `balance`, `last_paid`, `due_payment`, `last_trans_lt`, `code`, `data`;
+/// - `stateInit`: `split_depth`, `special`, `code`, `data`, `library`.
+///
+/// Messages and StateInits can be built without the base `boc`. The options must be
+/// the same as the ones used to produce the JSON.
+#[wasm_bindgen(js_name = "encodeStructure")]
+pub fn encode_structure(
+    json: &str,
+    structure_type: OptionalStructureType,
+    options: OptionalDeserializationOptions,
+) -> Result<String, JsValue> {
+    let options = DeserializationOptions::from_js(&options).handle_error()?;
+    let json = serde_json::from_str::<Value>(json).handle_error()?;
+    let edited = match &json {
+        Value::Object(map) => map,
+        _ => return Err("Expected a JSON object").handle_error(),
+    };
+
+    let base = match edited.get("boc") {
+        Some(Value::String(boc)) => Some(crate::boc::parse_cell(boc).handle_error()?),
+        Some(Value::Null) | None => None,
+        Some(_) => return Err("Invalid `boc`: expected a base64 string").handle_error(),
+    };
+
+    let ty = match (structure_type.as_string(), &base) {
+        (Some(structure_type), _) => {
+            StructureType::from_str(structure_type.trim()).handle_error()?
+        }
+        (None, Some(cell)) => try_detect_type(cell).handle_error()?,
+        (None, None) => return Err("Structure type is required").handle_error(),
+    };
+
+    let cell = ty.encode(edited, base, &options).handle_error()?;
+    serialize_toc(&cell).map(base64::encode).handle_error()
+}
 
-    let slice: SliceData = ton_types::SliceData::load_cell(cell)?;
+fn try_detect_type(cell: &Cell) -> Result<StructureType> {
+    let slice: SliceData = ton_types::SliceData::load_cell(cell.clone())?;
     if matches!(slice.clone().get_next_u32(), Ok(tag) if tag == BLOCK_TAG) {
         return Ok(StructureType::Block);
     };
@@ -58,6 +240,7 @@ enum StructureType {
     Message,
     Transaction,
     Account,
+    StateInit,
 }
 
 impl FromStr for StructureType {
@@ -69,25 +252,377 @@ impl FromStr for StructureType {
             "message" => Self::Message,
             "transaction" => Self::Transaction,
             "account" => Self::Account,
+            "stateInit" => Self::StateInit,
             _ => return Err("Unknown structure type"),
         })
     }
 }
 
 impl StructureType {
-    fn deserialize(&self, boc: &[u8]) -> Result<Value> {
+    fn as_str(&self) -> &'static str {
         match self {
-            Self::Block => Block::construct_from_bytes(boc).and_then(serialize_block_full),
-            Self::Message => Message::construct_from_bytes(boc).and_then(serialize_message),
+            Self::Block => "block",
+            Self::Message => "message",
+            Self::Transaction => "transaction",
+            Self::Account => "account",
+            Self::StateInit => "stateInit",
+        }
+    }
+
+    fn deserialize(&self, cell: Cell, options: &DeserializationOptions) -> Result<Value> {
+        let mode = options.mode;
+        let mut value = match self {
+            Self::Block => Block::construct_from_cell(cell)
+                .and_then(|block| serialize_block_full(block, mode, &options.abis))?,
+            Self::Message => Message::construct_from_cell(cell)
+                .and_then(|message| serialize_message(message, mode, &options.abis))?,
+            Self::Transaction => Transaction::construct_from_cell(cell)
+                .and_then(|tr| serialize_transaction(tr, mode, &options.abis))?,
+            Self::Account => Account::construct_from_cell(cell)
+                .and_then(|account| serialize_account(account, mode))?,
+            Self::StateInit => StateInit::construct_from_cell(cell)
+                .and_then(|state_init| serialize_state_init(&state_init))?,
+        };
+        options.format(&mut value);
+        Ok(value)
+    }
+
+    fn encode(
+        &self,
+        edited: &Map<String, Value>,
+        base: Option<Cell>,
+        options: &DeserializationOptions,
+    ) -> Result<Cell> {
+        let patch = Patch {
+            edited,
+            options,
+            base: match &base {
+                Some(cell) => match self.deserialize(cell.clone(), options)? {
+                    Value::Object(map) => map,
+                    _ => Map::new(),
+                },
+                None => Map::new(),
+            },
+        };
+        let required_base = |base: Option<Cell>| {
+            base.ok_or_else(|| anyhow!("Base `boc` is required for this structure"))
+        };
+
+        match self {
+            Self::Block => Err(anyhow!("Block encoding is not supported")),
+            Self::Message => {
+                let message = match base {
+                    Some(cell) => Message::construct_from_cell(cell)?,
+                    None => Message::with_int_header(Default::default()),
+                };
+                patch_message(message, &patch)?.serialize()
+            }
             Self::Transaction => {
-                Transaction::construct_from_bytes(boc).and_then(serialize_transaction)
+                let tr = Transaction::construct_from_cell(required_base(base)?)?;
+                patch_transaction(tr, &patch)?.serialize()
+            }
+            Self::Account => {
+                let account = Account::construct_from_cell(required_base(base)?)?;
+                patch_account(account, &patch)?.serialize()
+            }
+            Self::StateInit => {
+                let state_init = match base {
+                    Some(cell) => StateInit::construct_from_cell(cell)?,
+                    None => StateInit::default(),
+                };
+                patch_state_init(state_init, &patch)?.serialize()
+            }
+        }
+    }
+}
+
+const AMOUNT_FIELDS: &[&str] = &[
+    "balance",
+    "value",
+    "grams",
+    "ihr_fee",
+    "fwd_fee",
+    "import_fee",
+    "total_fees",
+    "due_payment",
+    "storage_fees_collected",
+    "storage_fees_due",
+    "due_fees_collected",
+    "credit",
+    "gas_fees",
+    "total_fwd_fees",
+    "total_action_fees",
+    "msg_fees",
+    "fwd_fees",
+    "req_fwd_fees",
+    "fees_collected",
+];
+
+const LT_FIELDS: &[&str] = &[
+    "lt",
+    "created_lt",
+    "last_trans_lt",
+    "prev_trans_lt",
+    "start_lt",
+    "end_lt",
+];
+
+const NANOTOKENS: u128 = 1_000_000_000;
+
+struct DeserializationOptions {
+    mode: SerializationMode,
+    amounts: Option<AmountFormat>,
+    addresses: Option<crate::address::FriendlyFlags>,
+    lt: Option<LtFormat>,
+    abis: Vec<ton_abi::Contract>,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq)]
+enum AmountFormat {
+    Nano,
+    Tokens,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq)]
+enum LtFormat {
+    Decimal,
+    Hex,
+}
+
+impl Default for DeserializationOptions {
+    fn default() -> Self {
+        Self {
+            mode: SerializationMode::QServer,
+            amounts: None,
+            addresses: None,
+            lt: None,
+            abis: Vec::new(),
+        }
+    }
+}
+
+impl DeserializationOptions {
+    fn from_js(options: &JsValue) -> Result<Self> {
+        let mut result = Self::default();
+        if options.is_undefined() || options.is_null() {
+            return Ok(result);
+        }
+
+        let get = |key: &str| {
+            js_sys::Reflect::get(options, &JsValue::from_str(key)).unwrap_or(JsValue::UNDEFINED)
+        };
+
+        if let Some(mode) = get("mode").as_string() {
+            result.mode = match mode.as_str() {
+                "standard" => SerializationMode::Standart,
+                "qServer" => SerializationMode::QServer,
+                "debug" => SerializationMode::Debug,
+                _ => return Err(anyhow!("Unknown serialization mode")),
+            };
+        }
+        if let Some(amounts) = get("amounts").as_string() {
+            result.amounts = Some(match amounts.as_str() {
+                "nano" => AmountFormat::Nano,
+                "tokens" => AmountFormat::Tokens,
+                _ => return Err(anyhow!("Unknown amounts format")),
+            });
+        }
+        if let Some(addresses) = get("addresses").as_string() {
+            result.addresses = match addresses.as_str() {
+                "raw" => None,
+                "friendly" => Some(crate::address::FriendlyFlags {
+                    bounceable: get("bounceable").as_bool().unwrap_or(true),
+                    testnet: get("testnet").as_bool().unwrap_or_default(),
+                    url_safe: true,
+                }),
+                _ => return Err(anyhow!("Unknown addresses format")),
+            };
+        }
+        if let Some(lt) = get("lt").as_string() {
+            result.lt = Some(match lt.as_str() {
+                "decimal" => LtFormat::Decimal,
+                "hex" => LtFormat::Hex,
+                _ => return Err(anyhow!("Unknown lt format")),
+            });
+        }
+        if let Some(abis) = get("abis").dyn_ref::<js_sys::Array>() {
+            for abi in abis.iter() {
+                let abi = abi
+                    .as_string()
+                    .ok_or_else(|| anyhow!("Expected contract ABI JSON"))?;
+                result.abis.push(ton_abi::Contract::load(abi.as_bytes())?);
+            }
+        }
+
+        Ok(result)
+    }
+
+    fn format(&self, value: &mut Value) {
+        self.format_value(value, true)
+    }
+
+    /// Formats the value in place. Amounts are converted only outside of the extra
+    /// currency collections (`*_other` fields), which use their own decimals.
+    fn format_value(&self, value: &mut Value, native: bool) {
+        match value {
+            Value::Object(map) => {
+                for (key, value) in map.iter_mut() {
+                    if key == "body_decoded" {
+                        continue;
+                    }
+                    if value.is_object() || value.is_array() {
+                        self.format_value(value, native && !key.ends_with("_other"));
+                        continue;
+                    }
+
+                    let (field, decimal) = match key.strip_suffix("_dec") {
+                        Some(field) => (field, true),
+                        None => (key.as_str(), false),
+                    };
+                    if AMOUNT_FIELDS.contains(&field) {
+                        if !native {
+                            continue;
+                        }
+                        if let (Some(format), Some(amount)) =
+                            (self.amounts, self.read_number(value, decimal))
+                        {
+                            *value = match format {
+                                AmountFormat::Nano => amount.to_string(),
+                                AmountFormat::Tokens => format_tokens(amount),
+                            }
+                            .into();
+                        }
+                    } else if LT_FIELDS.contains(&field) {
+                        if let (Some(format), Some(lt)) =
+                            (self.lt, self.read_number(value, decimal))
+                        {
+                            *value = match format {
+                                LtFormat::Decimal => lt.to_string(),
+                                LtFormat::Hex => format!("0x{lt:x}"),
+                            }
+                            .into();
+                        }
+                    } else {
+                        self.format_address(value);
+                    }
+                }
             }
-            Self::Account => Account::construct_from_bytes(boc).and_then(serialize_account),
+            Value::Array(items) => items
+                .iter_mut()
+                .for_each(|item| self.format_value(item, native)),
+            value => self.format_address(value),
         }
     }
+
+    fn format_address(&self, value: &mut Value) {
+        let flags = match self.addresses {
+            Some(flags) => flags,
+            None => return,
+        };
+        let address = match value {
+            Value::String(string) => match string.split_once(':') {
+                Some((_, hex)) if hex.len() == 64 => MsgAddressInt::from_str(string).ok(),
+                _ => None,
+            },
+            _ => None,
+        };
+        if let Some(friendly) =
+            address.and_then(|address| crate::address::pack_friendly(&address, flags).ok())
+        {
+            *value = friendly.into();
+        }
+    }
+
+    fn read_number(&self, value: &Value, decimal: bool) -> Option<u128> {
+        match value {
+            Value::Number(number) => number.as_u64().map(u128::from),
+            Value::String(string)
+                if decimal || !matches!(self.mode, SerializationMode::QServer) =>
+            {
+                u128::from_str(string).ok()
+            }
+            Value::String(string) => parse_prefixed_hex(string),
+            _ => None,
+        }
+    }
+
+    fn parse_grams(&self, value: &Value) -> Result<Grams> {
+        let amount = match (self.amounts, value) {
+            (Some(AmountFormat::Tokens), Value::String(string)) => parse_tokens(string.trim())?,
+            // Fractional numbers lose precision in JSON, so they must be passed as strings
+            (Some(AmountFormat::Tokens), Value::Number(number)) => match number.as_u64() {
+                Some(tokens) => parse_tokens(&tokens.to_string())?,
+                None => return Err(anyhow!("fractional amounts must be strings")),
+            },
+            (amounts, value) => self.parse_number(value, amounts.is_some())?,
+        };
+        Grams::new(amount)
+    }
+
+    /// Parses an edited number. Unless the field was reformatted by the options,
+    /// the `QServer` mode strings are length-prefixed hex, e.g. `0764000000`.
+    fn parse_number(&self, value: &Value, formatted: bool) -> Result<u128> {
+        match value {
+            Value::String(string)
+                if !formatted
+                    && matches!(self.mode, SerializationMode::QServer)
+                    && !string.trim().starts_with("0x") =>
+            {
+                parse_prefixed_hex(string.trim())
+                    .ok_or_else(|| anyhow!("expected a length-prefixed hex number"))
+            }
+            value => parse_u128(value),
+        }
+    }
+}
+
+/// Parses a hex number with the length prefix produced by the `QServer` mode.
+fn parse_prefixed_hex(string: &str) -> Option<u128> {
+    let parse = |prefix_len: usize| {
+        let len = usize::from_str_radix(string.get(..prefix_len)?, 16).ok()? + 1;
+        match string.get(prefix_len..) {
+            Some(digits) if digits.len() == len => u128::from_str_radix(digits, 16).ok(),
+            _ => None,
+        }
+    };
+    parse(1).or_else(|| parse(2))
+}
+
+fn format_tokens(amount: u128) -> String {
+    let (int, frac) = (amount / NANOTOKENS, amount % NANOTOKENS);
+    if frac == 0 {
+        int.to_string()
+    } else {
+        let frac = format!("{frac:09}");
+        format!("{int}.{}", frac.trim_end_matches('0'))
+    }
 }
 
-pub fn serialize_block_full(block: Block) -> Result<Value> {
+fn parse_tokens(string: &str) -> Result<u128> {
+    let (int, frac) = string.split_once('.').unwrap_or((string, ""));
+    if frac.len() > 9 {
+        return Err(anyhow!("too many decimal places"));
+    }
+    let int = if int.is_empty() {
+        0
+    } else {
+        u128::from_str(int)?
+    };
+    let frac = if frac.is_empty() {
+        0
+    } else {
+        u128::from_str(&format!("{frac:0<9}"))?
+    };
+    int.checked_mul(NANOTOKENS)
+        .and_then(|int| int.checked_add(frac))
+        .ok_or_else(|| anyhow!("amount is too big"))
+}
+
+pub fn serialize_block_full(
+    block: Block,
+    mode: SerializationMode,
+    abis: &[ton_abi::Contract],
+) -> Result<Value> {
     let root_cell = block.serialize()?;
     let set = BlockSerializationSet {
         block: block.clone(),
@@ -102,8 +637,8 @@ pub fn serialize_block_full(block: Block) -> Result<Value> {
     in_msgs_descr.iterate_objects(|in_msg| {
         let message = in_msg.read_message()?;
         in_msgs.insert(
-            root_cell.repr_hash().to_hex_string(),
-            serialize_message(message)?,
+            message.serialize()?.repr_hash().to_hex_string(),
+            serialize_message(message, mode, abis)?,
         );
         Ok(true)
     })?;
@@ -112,8 +647,8 @@ pub fn serialize_block_full(block: Block) -> Result<Value> {
     out_msgs_descr.iterate_objects(|out_msg| {
         if let Some(message) = out_msg.read_message()? {
             out_msgs.insert(
-                root_cell.repr_hash().to_hex_string(),
-                serialize_message(message)?,
+                message.serialize()?.repr_hash().to_hex_string(),
+                serialize_message(message, mode, abis)?,
             );
         }
         Ok(true)
@@ -123,21 +658,25 @@ pub fn serialize_block_full(block: Block) -> Result<Value> {
     acc_blocks.iterate_objects(|block| {
         block.transactions().iterate_objects(|InRefValue(tr)| {
             transactions.insert(
-                root_cell.repr_hash().to_hex_string(),
-                serialize_transaction(tr)?,
+                tr.serialize()?.repr_hash().to_hex_string(),
+                serialize_transaction(tr, mode, abis)?,
             );
             Ok(true)
         })
     })?;
     Ok(serde_json::json!({
-        "block": db_serialize_block_ex("id", &set, SerializationMode::QServer)?,
+        "block": db_serialize_block_ex("id", &set, mode)?,
         "in_msgs": in_msgs,
         "out_msgs": out_msgs,
         "transactions": transactions,
     }))
 }
 
-pub fn serialize_message(message: Message) -> Result<Value> {
+pub fn serialize_message(
+    message: Message,
+    mode: SerializationMode,
+    abis: &[ton_abi::Contract],
+) -> Result<Value> {
     let root_cell = message.serialize()?;
     let set = MessageSerializationSet {
         message,
@@ -149,11 +688,20 @@ pub fn serialize_message(message: Message) -> Result<Value> {
         boc: serialize_toc(&root_cell)?,
         proof: None,
     };
-    let map = db_serialize_message_ex("id", &set, SerializationMode::QServer)?;
+    let mut map = db_serialize_message_ex("id", &set, mode)?;
+    if !abis.is_empty() {
+        if let Some(decoded) = decode_message_body(&set.message, abis) {
+            map.insert("body_decoded".to_string(), decoded);
+        }
+    }
     Ok(map.into())
 }
 
-pub fn serialize_transaction(tr: Transaction) -> Result<Value> {
+pub fn serialize_transaction(
+    tr: Transaction,
+    mode: SerializationMode,
+    abis: &[ton_abi::Contract],
+) -> Result<Value> {
     let root_cell = tr.serialize()?;
     let set = TransactionSerializationSetEx {
         transaction: &tr,
@@ -164,19 +712,86 @@ pub fn serialize_transaction(tr: Transaction) -> Result<Value> {
         boc: &serialize_toc(&root_cell)?,
         proof: None,
     };
-    let map = db_serialize_transaction_ex("id", set, SerializationMode::QServer)?;
+    let mut map = db_serialize_transaction_ex("id", set, mode)?;
+    if !abis.is_empty() {
+        if let Some(in_msg) = tr.read_in_msg()? {
+            map.insert(
+                "in_message".to_string(),
+                serialize_message(in_msg, mode, abis)?,
+            );
+        }
+
+        let mut out_messages = Vec::new();
+        tr.iterate_out_msgs(|message| {
+            out_messages.push(serialize_message(message, mode, abis)?);
+            Ok(true)
+        })?;
+        map.insert("out_messages".to_string(), out_messages.into());
+    }
     Ok(map.into())
 }
 
-pub fn serialize_account(account: Account) -> Result<Value> {
+/// Decodes the message body with the first matching contract ABI.
+///
+/// Inbound external messages are decoded as function calls, outbound external messages as
+/// function outputs or events, and internal messages are tried both ways.
+fn decode_message_body(message: &Message, abis: &[ton_abi::Contract]) -> Option<Value> {
+    let body = message.body()?;
+    let (try_input, try_output, internal) = match message.header() {
+        CommonMsgInfo::IntMsgInfo(_) => (true, true, true),
+        CommonMsgInfo::ExtInMsgInfo(_) => (true, false, false),
+        CommonMsgInfo::ExtOutMsgInfo(_) => (false, true, false),
+    };
+
+    let make_decoded = |ty: &str, index: usize, decoded: ton_abi::contract::DecodedMessage| {
+        serde_json::json!({
+            "type": ty,
+            "abi": index,
+            "name": decoded.function_name,
+            "value": ton_abi::token::Detokenizer::detokenize_to_json_value(&decoded.tokens)
+                .unwrap_or_default(),
+        })
+    };
+
+    for (index, contract) in abis.iter().enumerate() {
+        if try_input {
+            if let Ok(decoded) = contract.decode_input(body.clone(), internal, false) {
+                return Some(make_decoded("call", index, decoded));
+            }
+        }
+        if try_output {
+            if let Ok(decoded) = contract.decode_output(body.clone(), internal, false) {
+                let ty = if contract.events.contains_key(&decoded.function_name) {
+                    "event"
+                } else {
+                    "answer"
+                };
+                return Some(make_decoded(ty, index, decoded));
+            }
+        }
+    }
+
+    // External inbound bodies start with the signature and headers, so the
+    // function id can't be located without the ABI
+    let id = match message.header() {
+        CommonMsgInfo::ExtInMsgInfo(_) => None,
+        _ => body.clone().get_next_u32().ok(),
+    };
+    Some(serde_json::json!({
+        "type": "unknown",
+        "id": id.map(|id| format!("0x{id:08x}")),
+    }))
+}
+
+pub fn serialize_account(account: Account, mode: SerializationMode) -> Result<Value> {
     let set = AccountSerializationSet {
         account: account.clone(),
         prev_code_hash: None,
-        boc: Vec::new(),
+        boc: serialize_toc(&account.serialize()?)?,
         boc1: None,
         proof: None,
     };
-    let mut map = db_serialize_account_ex("id", &set, SerializationMode::QServer)?;
+    let mut map = db_serialize_account_ex("id", &set, mode)?;
     if let Some(stuff) = account.stuff() {
         map.insert("stuff_full".to_string(), serialize_account_stuff(stuff)?);
     }
@@ -206,21 +821,9 @@ fn serialize_account_storage(storage: &AccountStorage) -> Result<Value> {
             serde_json::json!({"type": "AccountUninit"})
         }
         AccountState::AccountActive { state_init, .. } => {
-            let special = state_init.special().map(|special| {
-                serde_json::json!({
-                    "tick": special.tick,
-                    "tock": special.tock,
-                })
-            });
             serde_json::json!({
                 "type": "AccountActive",
-                "state_init": {
-                    "split_depth": state_init.split_depth.clone().unwrap_or_default().as_u32(),
-                    "special": special,
-                    "code": base64::encode(serialize_toc(&state_init.code.clone().unwrap_or_default())?),
-                    "data": base64::encode(serialize_toc(&state_init.data.clone().unwrap_or_default())?),
-                    "library": base64::encode(serialize_toc(&state_init.library.serialize()?)?),
-                }
+                "state_init": serialize_state_init_fields(state_init)?,
             })
         }
         AccountState::AccountFrozen {
@@ -244,6 +847,54 @@ fn serialize_account_storage(storage: &AccountStorage) -> Result<Value> {
     Ok(map.into())
 }
 
+pub fn serialize_state_init(state_init: &StateInit) -> Result<Value> {
+    let root_cell = state_init.serialize()?;
+    let mut map = serialize_state_init_fields(state_init)?;
+    map.insert(
+        "hash".to_string(),
+        root_cell.repr_hash().to_hex_string().into(),
+    );
+    map.insert(
+        "boc".to_string(),
+        base64::encode(serialize_toc(&root_cell)?).into(),
+    );
+    Ok(map.into())
+}
+
+fn serialize_state_init_fields(state_init: &StateInit) -> Result<Map<String, Value>> {
+    let special = state_init.special().map(|special| {
+        serde_json::json!({
+            "tick": special.tick,
+            "tock": special.tock,
+        })
+    });
+
+    let mut map = Map::new();
+    map.insert(
+        "split_depth".to_string(),
+        state_init
+            .split_depth
+            .clone()
+            .unwrap_or_default()
+            .as_u32()
+            .into(),
+    );
+    map.insert("special".to_string(), special.into());
+    map.insert(
+        "code".to_string(),
+        base64::encode(serialize_toc(&state_init.code.clone().unwrap_or_default())?).into(),
+    );
+    map.insert(
+        "data".to_string(),
+        base64::encode(serialize_toc(&state_init.data.clone().unwrap_or_default())?).into(),
+    );
+    map.insert(
+        "library".to_string(),
+        base64::encode(serialize_toc(&state_init.library.serialize()?)?).into(),
+    );
+    Ok(map)
+}
+
 fn serialize_account_storage_info(storage_info: &StorageInfo) -> Result<Value> {
     Ok(serde_json::json!({
         "used": serde_json::json!({
@@ -282,3 +933,475 @@ fn serialize_ecc(ecc: &ExtraCurrencyCollection) -> Result<Vec<Map<String, Value>
     })?;
     Ok(other)
 }
+
+struct Patch<'a> {
+    edited: &'a Map<String, Value>,
+    options: &'a DeserializationOptions,
+    base: Map<String, Value>,
+}
+
+impl Patch<'_> {
+    fn get<T>(&self, key: &str, parse: impl FnOnce(&Value) -> Result<T>) -> Result<Option<T>> {
+        match self.edited.get(key) {
+            Some(value) if self.base.get(key) != Some(value) => parse(value)
+                .map(Some)
+                .map_err(|e| anyhow!("Invalid `{key}`: {e}")),
+            _ => Ok(None),
+        }
+    }
+
+    fn get_lt(&self, key: &str) -> Result<Option<u64>> {
+        self.get(key, |value| {
+            let lt = self
+                .options
+                .parse_number(value, self.options.lt.is_some())?;
+            u64::try_from(lt).map_err(|_| anyhow!("value is too big"))
+        })
+    }
+
+    fn get_grams(&self, key: &str) -> Result<Option<Grams>> {
+        self.get(key, |value| self.options.parse_grams(value))
+    }
+
+    fn get_optional_grams(&self, key: &str) -> Result<Option<Option<Grams>>> {
+        self.get(key, |value| match value {
+            Value::Null => Ok(None),
+            value => self.options.parse_grams(value).map(Some),
+        })
+    }
+}
+
+fn patch_message(message: Message, patch: &Patch) -> Result<Message> {
+    let mut header = message.header().clone();
+    match &mut header {
+        CommonMsgInfo::IntMsgInfo(header) => {
+            if let Some(src) = patch.get("src", parse_address_or_none)? {
+                header.src = src;
+            }
+            if let Some(dst) = patch.get("dst", parse_address)? {
+                header.dst = dst;
+            }
+            if let Some(value) = patch.get_grams("value")? {
+                header.value.grams = value;
+            }
+            if let Some(ihr_disabled) = patch.get("ihr_disabled", parse_bool)? {
+                header.ihr_disabled = ihr_disabled;
+            }
+            if let Some(bounce) = patch.get("bounce", parse_bool)? {
+                header.bounce = bounce;
+            }
+            if let Some(bounced) = patch.get("bounced", parse_bool)? {
+                header.bounced = bounced;
+            }
+            if let Some(ihr_fee) = patch.get_grams("ihr_fee")? {
+                header.ihr_fee = ihr_fee;
+            }
+            if let Some(fwd_fee) = patch.get_grams("fwd_fee")? {
+                header.fwd_fee = fwd_fee;
+            }
+            if let Some(created_lt) = patch.get_lt("created_lt")? {
+                header.created_lt = created_lt;
+            }
+            if let Some(created_at) = patch.get("created_at", parse_u32)? {
+                header.created_at = created_at.into();
+            }
+        }
+        CommonMsgInfo::ExtInMsgInfo(header) => {
+            if let Some(dst) = patch.get("dst", parse_address)? {
+                header.dst = dst;
+            }
+            if let Some(import_fee) = patch.get_grams("import_fee")? {
+                header.import_fee = import_fee;
+            }
+        }
+        CommonMsgInfo::ExtOutMsgInfo(header) => {
+            if let Some(src) = patch.get("src", parse_address_or_none)? {
+                header.src = src;
+            }
+            if let Some(created_lt) = patch.get_lt("created_lt")? {
+                header.created_lt = created_lt;
+            }
+            if let Some(created_at) = patch.get("created_at", parse_u32)? {
+                header.created_at = created_at.into();
+            }
+        }
+    }
+
+    let state_init = match patch.get("init", parse_optional_cell)? {
+        Some(cell) => cell.map(StateInit::construct_from_cell).transpose()?,
+        None => message.state_init().cloned(),
+    };
+    let body = match patch.get("body", parse_optional_cell)? {
+        Some(body) => body,
+        None => message.body().map(SliceData::into_cell),
+    };
+
+    let mut result = match header {
+        CommonMsgInfo::IntMsgInfo(header) => Message::with_int_header(header),
+        CommonMsgInfo::ExtInMsgInfo(header) => Message::with_ext_in_header(header),
+        CommonMsgInfo::ExtOutMsgInfo(header) => Message::with_ext_out_header(header),
+    };
+    if let Some(state_init) = state_init {
+        result.set_state_init(state_init);
+    }
+    if let Some(body) = body {
+        result.set_body(SliceData::load_cell(body)?);
+    }
+    Ok(result)
+}
+
+fn patch_transaction(mut tr: Transaction, patch: &Patch) -> Result<Transaction> {
+    if let Some(address) = patch.get("account_addr", parse_address)? {
+        tr.account_addr = address.address();
+    }
+    if let Some(lt) = patch.get_lt("lt")? {
+        tr.lt = lt;
+    }
+    if let Some(prev_trans_lt) = patch.get_lt("prev_trans_lt")? {
+        tr.prev_trans_lt = prev_trans_lt;
+    }
+    if let Some(prev_trans_hash) = patch.get("prev_trans_hash", parse_hash)? {
+        tr.prev_trans_hash = prev_trans_hash;
+    }
+    if let Some(now) = patch.get("now", parse_u32)? {
+        tr.now = now;
+    }
+    if let Some(orig_status) = patch.get("orig_status", parse_account_status)? {
+        tr.orig_status = orig_status;
+    }
+    if let Some(end_status) = patch.get("end_status", parse_account_status)? {
+        tr.end_status = end_status;
+    }
+    if let Some(total_fees) = patch.get_grams("total_fees")? {
+        tr.total_fees.grams = total_fees;
+    }
+    if let Some(in_msg) = patch.get("in_msg_boc", parse_optional_cell)? {
+        let in_msg = in_msg.map(Message::construct_from_cell).transpose()?;
+        tr.write_in_msg(in_msg.as_ref())?;
+    }
+    if let Some(out_msgs) = patch.get("out_msgs_boc", parse_cells)? {
+        tr.out_msgs = Default::default();
+        tr.outmsg_cnt = 0;
+        for cell in out_msgs {
+            tr.add_out_message(&Message::construct_from_cell(cell)?)?;
+        }
+    }
+    Ok(tr)
+}
+
+fn patch_account(mut account: Account, patch: &Patch) -> Result<Account> {
+    let stuff = match &mut account {
+        Account::Account(stuff) => stuff,
+        Account::AccountNone => return Err(anyhow!("Cannot edit a non-existent account")),
+    };
+
+    if let Some(address) = patch.get("id", parse_address)? {
+        stuff.addr = address;
+    }
+    if let Some(balance) = patch.get_grams("balance")? {
+        stuff.storage.balance.grams = balance;
+    }
+    if let Some(last_paid) = patch.get("last_paid", parse_u32)? {
+        stuff.storage_stat.last_paid = last_paid;
+    }
+    if let Some(due_payment) = patch.get_optional_grams("due_payment")? {
+        stuff.storage_stat.due_payment = due_payment;
+    }
+    if let Some(last_trans_lt) = patch.get_lt("last_trans_lt")? {
+        stuff.storage.last_trans_lt = last_trans_lt;
+    }
+
+    let code = patch.get("code", parse_optional_cell)?;
+    let data = patch.get("data", parse_optional_cell)?;
+    if code.is_some() || data.is_some() {
+        let state_init = match &mut stuff.storage.state {
+            AccountState::AccountActive { state_init } => state_init,
+            _ => {
+                return Err(anyhow!(
+                    "Code and data can only be edited for active accounts"
+                ))
+            }
+        };
+        if let Some(code) = code {
+            state_init.code = code;
+        }
+        if let Some(data) = data {
+            state_init.data = data;
+        }
+    }
+
+    account.update_storage_stat()?;
+    Ok(account)
+}
+
+fn patch_state_init(mut state_init: StateInit, patch: &Patch) -> Result<StateInit> {
+    if let Some(split_depth) = patch.get("split_depth", parse_u32)? {
+        state_init.split_depth = match split_depth {
+            0 => None,
+            split_depth => Some(Number5::new(split_depth)?),
+        };
+    }
+    if let Some(special) = patch.get("special", parse_special)? {
+        state_init.special = special;
+    }
+    if let Some(code) = patch.get("code", parse_optional_cell)? {
+        state_init.code = code;
+    }
+    if let Some(data) = patch.get("data", parse_optional_cell)? {
+        state_init.data = data;
+    }
+    if let Some(library) = patch.get("library", parse_optional_cell)? {
+        state_init.library = match library {
+            Some(cell) => StateInitLib::construct_from_cell(cell)?,
+            None => Default::default(),
+        };
+    }
+    Ok(state_init)
+}
+
+fn parse_u128(value: &Value) -> Result<u128> {
+    match value {
+        Value::Number(number) => number
+            .as_u64()
+            .map(u128::from)
+            .ok_or_else(|| anyhow!("expected an unsigned integer")),
+        Value::String(string) => {
+            let string = string.trim();
+            Ok(match string.strip_prefix("0x") {
+                Some(hex) => u128::from_str_radix(hex, 16)?,
+                None => u128::from_str(string)?,
+            })
+        }
+        _ => Err(anyhow!("expected a number or a string")),
+    }
+}
+
+fn parse_u32(value: &Value) -> Result<u32> {
+    u32::try_from(parse_u128(value)?).map_err(|_| anyhow!("value is too big"))
+}
+
+fn parse_bool(value: &Value) -> Result<bool> {
+    value.as_bool().ok_or_else(|| anyhow!("expected a boolean"))
+}
+
+fn parse_str(value: &Value) -> Result<&str> {
+    value
+        .as_str()
+        .map(str::trim)
+        .ok_or_else(|| anyhow!("expected a string"))
+}
+
+fn parse_hash(value: &Value) -> Result<UInt256> {
+    UInt256::from_str(parse_str(value)?)
+}
+
+fn parse_address(value: &Value) -> Result<MsgAddressInt> {
+    crate::address::parse_address(parse_str(value)?)
+}
+
+fn parse_address_or_none(value: &Value) -> Result<MsgAddressIntOrNone> {
+    match value {
+        Value::Null => Ok(MsgAddressIntOrNone::None),
+        Value::String(string) if string.trim().is_empty() => Ok(MsgAddressIntOrNone::None),
+        value => parse_address(value).map(MsgAddressIntOrNone::Some),
+    }
+}
+
+fn parse_account_status(value: &Value) -> Result<AccountStatus> {
+    let status = match value {
+        Value::Number(_) => match parse_u32(value)? {
+            0 => "uninit",
+            1 => "active",
+            2 => "frozen",
+            3 => "nonexist",
+            _ => return Err(anyhow!("unknown account status")),
+        }
+        .to_string(),
+        value => parse_str(value)?.to_lowercase(),
+    };
+    Ok(match status.as_str() {
+        "uninit" => AccountStatus::AccStateUninit,
+        "active" => AccountStatus::AccStateActive,
+        "frozen" => AccountStatus::AccStateFrozen,
+        "nonexist" => AccountStatus::AccStateNonexist,
+        _ => return Err(anyhow!("unknown account status")),
+    })
+}
+
+fn parse_special(value: &Value) -> Result<Option<TickTock>> {
+    match value {
+        Value::Null => Ok(None),
+        Value::Object(special) => {
+            let flag = |name: &str| special.get(name).map(parse_bool).unwrap_or(Ok(false));
+            Ok(Some(TickTock {
+                tick: flag("tick")?,
+                tock: flag("tock")?,
+            }))
+        }
+        _ => Err(anyhow!("expected an object or null")),
+    }
+}
+
+fn parse_optional_cell(value: &Value) -> Result<Option<Cell>> {
+    match value {
+        Value::Null => Ok(None),
+        value => match parse_str(value)? {
+            "" => Ok(None),
+            boc => crate::boc::parse_cell(boc).map(Some),
+        },
+    }
+}
+
+fn parse_cells(value: &Value) -> Result<Vec<Cell>> {
+    match value {
+        Value::Array(items) => items
+            .iter()
+            .map(|item| parse_optional_cell(item)?.ok_or_else(|| anyhow!("empty BOC")))
+            .collect(),
+        _ => Err(anyhow!("expected an array of BOCs")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_message() -> Message {
+        let src = MsgAddressInt::from_str(&format!("0:{}", "11".repeat(32))).unwrap();
+        let dst = MsgAddressInt::from_str(&format!("-1:{}", "22".repeat(32))).unwrap();
+        let mut header = InternalMessageHeader::with_addresses(
+            src,
+            dst,
+            CurrencyCollection::with_grams(1_000_000_000),
+        );
+        header.created_lt = 0x1234;
+        header.created_at = 1_700_000_000u32.into();
+
+        let mut message = Message::with_int_header(header);
+        message.set_body(SliceData::new(vec![0xde, 0xad, 0xbe, 0xef, 0x80]));
+        message
+    }
+
+    fn round_trip(
+        ty: StructureType,
+        cell: Cell,
+        options: &DeserializationOptions,
+        edit: impl FnOnce(&mut Map<String, Value>),
+    ) -> Cell {
+        let mut edited = match ty.deserialize(cell.clone(), options).unwrap() {
+            Value::Object(map) => map,
+            value => panic!("expected an object, got {value}"),
+        };
+        edit(&mut edited);
+        ty.encode(&edited, Some(cell), options).unwrap()
+    }
+
+    #[test]
+    fn unchanged_structures_round_trip() {
+        let message = make_message().serialize().unwrap();
+
+        let mut state_init = StateInit::default();
+        state_init.code = Some(SliceData::new(vec![0xff, 0x00, 0x80]).into_cell());
+        let state_init = state_init.serialize().unwrap();
+
+        for options in [
+            DeserializationOptions::default(),
+            DeserializationOptions {
+                mode: SerializationMode::Standart,
+                amounts: Some(AmountFormat::Tokens),
+                lt: Some(LtFormat::Hex),
+                ..Default::default()
+            },
+            DeserializationOptions {
+                mode: SerializationMode::Debug,
+                ..Default::default()
+            },
+        ] {
+            for (ty, cell) in [
+                (StructureType::Message, &message),
+                (StructureType::StateInit, &state_init),
+            ] {
+                let encoded = round_trip(ty, cell.clone(), &options, |_| {});
+                assert_eq!(
+                    serialize_toc(&encoded).unwrap(),
+                    serialize_toc(cell).unwrap()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn patched_message_fields() {
+        let cell = make_message().serialize().unwrap();
+
+        let encoded = round_trip(
+            StructureType::Message,
+            cell.clone(),
+            &DeserializationOptions::default(),
+            |edited| {
+                edited.insert("created_lt".to_string(), "764000000".into());
+                edited.insert("value".to_string(), "0764000000".into());
+                edited.insert("bounce".to_string(), true.into());
+            },
+        );
+        let message = Message::construct_from_cell(encoded).unwrap();
+        let header = message.int_header().unwrap();
+        assert_eq!(header.created_lt, 0x64000000);
+        assert_eq!(header.value.grams, Grams::new(0x64000000).unwrap());
+        assert!(header.bounce);
+
+        let encoded = round_trip(
+            StructureType::Message,
+            cell.clone(),
+            &DeserializationOptions {
+                amounts: Some(AmountFormat::Tokens),
+                ..Default::default()
+            },
+            |edited| {
+                edited.insert("value".to_string(), "1.5".into());
+            },
+        );
+        let message = Message::construct_from_cell(encoded).unwrap();
+        let header = message.int_header().unwrap();
+        assert_eq!(header.value.grams, Grams::new(1_500_000_000).unwrap());
+        assert_eq!(header.created_lt, 0x1234);
+
+        let options = DeserializationOptions {
+            mode: SerializationMode::Debug,
+            amounts: Some(AmountFormat::Nano),
+            ..Default::default()
+        };
+        let value = StructureType::Message
+            .deserialize(cell.clone(), &options)
+            .unwrap();
+        assert_eq!(value["value"], "1000000000");
+
+        let encoded = round_trip(StructureType::Message, cell, &options, |edited| {
+            edited.insert("created_lt".to_string(), "1700000000".into());
+            edited.insert("value".to_string(), "2500000000".into());
+        });
+        let message = Message::construct_from_cell(encoded).unwrap();
+        let header = message.int_header().unwrap();
+        assert_eq!(header.created_lt, 1_700_000_000);
+        assert_eq!(header.value.grams, Grams::new(2_500_000_000).unwrap());
+    }
+
+    #[test]
+    fn prefixed_hex() {
+        assert_eq!(parse_prefixed_hex("0764000000"), Some(0x64000000));
+        assert_eq!(parse_prefixed_hex("764000000"), Some(0x64000000));
+        assert_eq!(parse_prefixed_hex("0f"), Some(0xf));
+        assert_eq!(parse_prefixed_hex("1234"), None);
+
+        let options = DeserializationOptions::default();
+        assert_eq!(
+            options.parse_number(&"0764000000".into(), false).unwrap(),
+            0x64000000
+        );
+        assert_eq!(
+            options.parse_number(&"764000000".into(), true).unwrap(),
+            764000000
+        );
+        assert!(options.parse_number(&"12".into(), false).is_err());
+    }
+}
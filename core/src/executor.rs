@@ -1,10 +1,304 @@
+use std::collections::BTreeMap;
+
+use case::CaseExt;
+use serde_json::Value;
 use shared::*;
+use ton_abi::contract::{AbiVersion, ABI_VERSION_1_0, ABI_VERSION_2_1, ABI_VERSION_2_4};
+use ton_abi::{Param, ParamType};
 use wasm_bindgen::prelude::*;
 
+#[wasm_bindgen(typescript_custom_section)]
+const ABI_FINDING: &str = r#"
+export type AbiFinding = {
+    severity: 'error' | 'warning' | 'info',
+    code: string,
+    message: string,
+};
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "AbiFinding")]
+    pub type AbiFinding;
+}
+
+/// Loads the contract ABI and returns the linter findings.
+///
+/// Throws only if the ABI can't be loaded at all.
 #[wasm_bindgen(js_name = "checkAbi")]
-pub fn check_abi(abi: &str) -> Result<(), JsValue> {
-    ton_abi::Contract::load(abi.as_bytes()).handle_error()?;
-    Ok(())
+pub fn check_abi(abi: &str) -> Result<Vec<AbiFinding>, JsValue> {
+    let contract = ton_abi::Contract::load(abi.as_bytes()).handle_error()?;
+    let raw = serde_json::from_str::<Value>(abi).handle_error()?;
+
+    Ok(lint_abi(&contract, &raw)
+        .into_iter()
+        .map(|finding| {
+            ObjectBuilder::new()
+                .set("severity", finding.severity.as_str())
+                .set("code", finding.code)
+                .set("message", finding.message)
+                .build()
+                .unchecked_into()
+        })
+        .collect())
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Severity {
+    Error,
+    Warning,
+    Info,
+}
+
+impl Severity {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Error => "error",
+            Self::Warning => "warning",
+            Self::Info => "info",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AbiLintFinding {
+    pub severity: Severity,
+    pub code: &'static str,
+    pub message: String,
+}
+
+pub fn lint_abi(contract: &ton_abi::Contract, raw: &Value) -> Vec<AbiLintFinding> {
+    let mut findings = Vec::new();
+    let mut push = |severity, code, message| {
+        findings.push(AbiLintFinding {
+            severity,
+            code,
+            message,
+        })
+    };
+
+    // Entries which are silently overwritten during loading
+    for section in ["functions", "events"] {
+        let mut names = BTreeMap::<&str, usize>::new();
+        for item in raw[section].as_array().into_iter().flatten() {
+            if let Some(name) = item["name"].as_str() {
+                *names.entry(name).or_default() += 1;
+            }
+        }
+        for (name, count) in names.into_iter().filter(|(_, count)| *count > 1) {
+            push(
+                Severity::Error,
+                "duplicate-name",
+                format!("`{name}` is declared {count} times in `{section}`"),
+            );
+        }
+    }
+
+    let mut function_ids = BTreeMap::<u32, Vec<&str>>::new();
+    for function in contract.functions.values() {
+        function_ids
+            .entry(function.input_id)
+            .or_default()
+            .push(&function.name);
+    }
+    let mut event_ids = BTreeMap::<u32, Vec<&str>>::new();
+    for event in contract.events.values() {
+        event_ids.entry(event.id).or_default().push(&event.name);
+    }
+    for (kind, ids) in [("function", function_ids), ("event", event_ids)] {
+        for (id, mut names) in ids.into_iter().filter(|(_, names)| names.len() > 1) {
+            names.sort_unstable();
+            push(
+                Severity::Error,
+                "duplicate-id",
+                format!("{kind}s {} have the same id 0x{id:08x}", names.join(", ")),
+            );
+        }
+    }
+
+    let mut converted_names = BTreeMap::<String, Vec<&str>>::new();
+    for name in contract.functions.keys().chain(contract.events.keys()) {
+        converted_names
+            .entry(name.to_snake().to_lowercase())
+            .or_default()
+            .push(name);
+    }
+    for (converted, mut names) in converted_names {
+        names.sort_unstable();
+        names.dedup();
+        if names.len() > 1 {
+            push(
+                Severity::Warning,
+                "case-collision",
+                format!(
+                    "{} collide as `{converted}` after case conversion",
+                    names.join(", ")
+                ),
+            );
+        }
+    }
+
+    let version = contract.abi_version;
+    let mut check_params = |context: String, params: &[Param]| {
+        for param in params {
+            let required = min_abi_version(&param.kind);
+            if required > version {
+                push(
+                    Severity::Error,
+                    "unsupported-type",
+                    format!(
+                        "{context}: `{}` of type `{}` requires ABI {required}, declared {version}",
+                        param.name,
+                        param.kind.type_signature(),
+                    ),
+                );
+            }
+        }
+    };
+    for function in contract.functions.values() {
+        check_params(format!("function `{}`", function.name), &function.inputs);
+        check_params(format!("function `{}`", function.name), &function.outputs);
+    }
+    for event in contract.events.values() {
+        check_params(format!("event `{}`", event.name), &event.inputs);
+    }
+    check_params("fields".to_string(), &contract.fields);
+
+    // Headers
+    let headers = contract
+        .header
+        .iter()
+        .map(|param| param.name.as_str())
+        .collect::<Vec<_>>();
+    if version == ABI_VERSION_1_0 && !headers.is_empty() {
+        push(
+            Severity::Error,
+            "header-version",
+            "ABI 1.0 doesn't support custom headers".to_string(),
+        );
+    }
+    match raw["version"].as_str() {
+        Some(declared)
+            if raw["ABI version"].as_u64()
+                != declared
+                    .split('.')
+                    .next()
+                    .and_then(|major| major.parse().ok()) =>
+        {
+            push(
+                Severity::Error,
+                "version-mismatch",
+                format!("`ABI version` contradicts `version` {declared}"),
+            );
+        }
+        None if version.major >= 2 && !headers.is_empty() => push(
+            Severity::Warning,
+            "missing-version",
+            "headers are declared without the minor `version`".to_string(),
+        ),
+        _ => {}
+    }
+    for (i, name) in headers.iter().enumerate() {
+        if headers[..i].contains(name) {
+            push(
+                Severity::Error,
+                "duplicate-header",
+                format!("header `{name}` is declared more than once"),
+            );
+        }
+    }
+    if headers.contains(&"expire") && !headers.contains(&"time") {
+        push(
+            Severity::Warning,
+            "header-expire",
+            "`expire` header without `time` doesn't protect from replay".to_string(),
+        );
+    }
+
+    // Persistent fields
+    if !contract.fields.is_empty() {
+        let fields = &contract.fields;
+        if !is_field(fields.first(), "_pubkey", &ParamType::Uint(256)) {
+            push(
+                Severity::Warning,
+                "fields-layout",
+                "the first field is expected to be `_pubkey: uint256`".to_string(),
+            );
+        }
+        match fields.iter().position(|field| field.name == "_timestamp") {
+            Some(1) if is_field(fields.get(1), "_timestamp", &ParamType::Uint(64)) => {}
+            Some(_) => push(
+                Severity::Warning,
+                "fields-layout",
+                "`_timestamp` is expected to be the second field of type `uint64`".to_string(),
+            ),
+            None if headers.contains(&"time") && version >= ABI_VERSION_2_1 => push(
+                Severity::Info,
+                "fields-layout",
+                "`time` header is declared but there is no `_timestamp` field".to_string(),
+            ),
+            None => {}
+        }
+    }
+
+    // TIP-6
+    let interfaces = crate::tip6::match_interfaces(contract);
+    let supports_interface = contract.functions.contains_key("supportsInterface");
+    for interface in &interfaces {
+        match interface.implementation() {
+            _ if interface.name == crate::tip6::TIP6_SID => {
+                if supports_interface && !interface.missing.is_empty() {
+                    push(
+                        Severity::Error,
+                        "tip6-signature",
+                        "`supportsInterface` must be `(uint32 answerId, uint32 interfaceID) -> (bool)`"
+                            .to_string(),
+                    );
+                }
+            }
+            "full" if !supports_interface => push(
+                Severity::Warning,
+                "tip6-missing",
+                format!(
+                    "{} is implemented but `supportsInterface` is missing",
+                    interface.name
+                ),
+            ),
+            "partial" => push(
+                Severity::Info,
+                "tip6-partial",
+                format!(
+                    "{} is implemented partially, missing: {}",
+                    interface.name,
+                    interface.missing.join(", ")
+                ),
+            ),
+            _ => {}
+        }
+    }
+
+    findings
+}
+
+fn is_field(field: Option<&Param>, name: &str, kind: &ParamType) -> bool {
+    matches!(field, Some(field) if field.name == name && &field.kind == kind)
+}
+
+fn min_abi_version(kind: &ParamType) -> AbiVersion {
+    match kind {
+        ParamType::String | ParamType::VarUint(_) | ParamType::VarInt(_) => ABI_VERSION_2_1,
+        ParamType::Optional(inner) => std::cmp::max(ABI_VERSION_2_1, min_abi_version(inner)),
+        ParamType::Ref(inner) => std::cmp::max(ABI_VERSION_2_4, min_abi_version(inner)),
+        ParamType::AddressStd => ABI_VERSION_2_4,
+        ParamType::Array(inner) | ParamType::FixedArray(inner, _) => min_abi_version(inner),
+        ParamType::Map(key, value) => std::cmp::max(min_abi_version(key), min_abi_version(value)),
+        ParamType::Tuple(params) => params
+            .iter()
+            .map(|param| min_abi_version(&param.kind))
+            .max()
+            .unwrap_or(ABI_VERSION_1_0),
+        _ => ABI_VERSION_1_0,
+    }
 }
 
 #[wasm_bindgen(typescript_custom_section)]
@@ -46,6 +340,545 @@ pub fn get_contract_functions(abi: &str) -> Result<Vec<FunctionEntry>, JsValue>
     Ok(result)
 }
 
+#[wasm_bindgen(typescript_custom_section)]
+const CONTRACT_ABI: &str = r#"
+export type ContractAbiFunction = {
+    name: string,
+    inputId: number,
+    outputId: number,
+    headers: ever.AbiParam[],
+    inputs: ever.AbiParam[],
+    outputs: ever.AbiParam[],
+    responsible: boolean,
+};
+
+export type ContractAbiEvent = {
+    name: string,
+    id: number,
+    inputs: ever.AbiParam[],
+};
+
+export type ContractAbiGetter = {
+    name: string,
+    inputs: ever.AbiParam[],
+    outputs: ever.AbiParam[],
+};
+
+export type ContractAbi = {
+    version: {
+        major: number,
+        minor: number,
+    },
+    headers: ever.AbiParam[],
+    functions: ContractAbiFunction[],
+    events: ContractAbiEvent[],
+    fields: (ever.AbiParam & { init: boolean })[],
+    getters: ContractAbiGetter[],
+};
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "ContractAbi")]
+    pub type ContractAbi;
+}
+
+/// Returns the full description of the contract ABI.
+///
+/// Functions and events keep the order of the JSON. A function is considered responsible
+/// when its first input is `answerId: uint32`.
+#[wasm_bindgen(js_name = "getContractAbi")]
+pub fn get_contract_abi(abi: &str) -> Result<ContractAbi, JsValue> {
+    use crate::serializer::make_params;
+
+    let contract = ton_abi::Contract::load(abi.as_bytes()).handle_error()?;
+    let raw = serde_json::from_str::<Value>(abi).handle_error()?;
+
+    let functions = js_sys::Array::new();
+    for name in ordered_names(&raw["functions"], contract.functions.keys()) {
+        let function = match contract.functions.get(name) {
+            Some(function) => function,
+            None => continue,
+        };
+        let responsible = matches!(
+            function.inputs.first(),
+            Some(Param { name, kind: ParamType::Uint(32) }) if name == "answerId"
+        );
+        functions.push(
+            &ObjectBuilder::new()
+                .set("name", name)
+                .set("inputId", function.input_id)
+                .set("outputId", function.output_id)
+                .set("headers", make_params(function.header.clone()))
+                .set("inputs", make_params(function.inputs.clone()))
+                .set("outputs", make_params(function.outputs.clone()))
+                .set("responsible", responsible)
+                .build(),
+        );
+    }
+
+    let events = js_sys::Array::new();
+    for name in ordered_names(&raw["events"], contract.events.keys()) {
+        if let Some(event) = contract.events.get(name) {
+            events.push(
+                &ObjectBuilder::new()
+                    .set("name", name)
+                    .set("id", event.id)
+                    .set("inputs", make_params(event.inputs.clone()))
+                    .build(),
+            );
+        }
+    }
+
+    let fields = js_sys::Array::new();
+    let raw_fields = raw["fields"].as_array().cloned().unwrap_or_default();
+    for (field, raw_field) in make_params(contract.fields.clone())
+        .unchecked_into::<js_sys::Array>()
+        .iter()
+        .zip(raw_fields.iter().map(Some).chain(std::iter::repeat(None)))
+    {
+        let init = raw_field
+            .and_then(|field| field["init"].as_bool())
+            .unwrap_or_default();
+        js_sys::Reflect::set(&field, &JsValue::from_str("init"), &JsValue::from(init)).ok();
+        fields.push(&field);
+    }
+
+    let getters = js_sys::Array::new();
+    for getter in raw["getters"].as_array().into_iter().flatten() {
+        let params = |key: &str| -> Result<Vec<Param>, JsValue> {
+            match getter.get(key) {
+                Some(params) => serde_json::from_value(params.clone()).handle_error(),
+                None => Ok(Vec::new()),
+            }
+        };
+        getters.push(
+            &ObjectBuilder::new()
+                .set("name", getter["name"].as_str().unwrap_or_default())
+                .set("inputs", make_params(params("inputs")?))
+                .set("outputs", make_params(params("outputs")?))
+                .build(),
+        );
+    }
+
+    Ok(ObjectBuilder::new()
+        .set(
+            "version",
+            ObjectBuilder::new()
+                .set("major", contract.abi_version.major)
+                .set("minor", contract.abi_version.minor)
+                .build(),
+        )
+        .set("headers", make_params(contract.header.clone()))
+        .set("functions", functions)
+        .set("events", events)
+        .set("fields", fields)
+        .set("getters", getters)
+        .build()
+        .unchecked_into())
+}
+
+/// Lists names in the order of the JSON section, followed by the rest of the loaded names.
+fn ordered_names<'a>(section: &'a Value, loaded: impl Iterator<Item = &'a String>) -> Vec<&'a str> {
+    let mut names = section
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|item| item["name"].as_str())
+        .collect::<Vec<_>>();
+    let mut rest = loaded
+        .map(String::as_str)
+        .filter(|name| !names.contains(name))
+        .collect::<Vec<_>>();
+    rest.sort_unstable();
+    names.extend(rest);
+
+    let mut unique = Vec::with_capacity(names.len());
+    for name in names {
+        if !unique.contains(&name) {
+            unique.push(name);
+        }
+    }
+    unique
+}
+
+#[wasm_bindgen(typescript_custom_section)]
+const ABI_CHANGE: &str = r#"
+export type AbiChange = {
+    section: 'version' | 'header' | 'function' | 'event' | 'field',
+    name: string,
+    change: 'added' | 'removed' | 'changed',
+    breaking: boolean,
+    message: string,
+    oldId?: number,
+    newId?: number,
+};
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "AbiChange")]
+    pub type AbiChange;
+}
+
+/// Compares two versions of the contract ABI.
+#[wasm_bindgen(js_name = "diffAbi")]
+pub fn compare_abi(old_abi: &str, new_abi: &str) -> Result<Vec<AbiChange>, JsValue> {
+    let old = ton_abi::Contract::load(old_abi.as_bytes()).handle_error()?;
+    let new = ton_abi::Contract::load(new_abi.as_bytes()).handle_error()?;
+
+    Ok(diff_abi(&old, &new)
+        .into_iter()
+        .map(|item| {
+            ObjectBuilder::new()
+                .set("section", item.section.as_str())
+                .set("name", item.name)
+                .set("change", item.change.as_str())
+                .set("breaking", item.breaking)
+                .set("message", item.message)
+                .set("oldId", item.old_id)
+                .set("newId", item.new_id)
+                .build()
+                .unchecked_into()
+        })
+        .collect())
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum AbiSection {
+    Version,
+    Header,
+    Function,
+    Event,
+    Field,
+}
+
+impl AbiSection {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Version => "version",
+            Self::Header => "header",
+            Self::Function => "function",
+            Self::Event => "event",
+            Self::Field => "field",
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum AbiChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+impl AbiChangeKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Added => "added",
+            Self::Removed => "removed",
+            Self::Changed => "changed",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AbiDiffItem {
+    pub section: AbiSection,
+    pub name: String,
+    pub change: AbiChangeKind,
+    pub breaking: bool,
+    pub message: String,
+    pub old_id: Option<u32>,
+    pub new_id: Option<u32>,
+}
+
+pub fn diff_abi(old: &ton_abi::Contract, new: &ton_abi::Contract) -> Vec<AbiDiffItem> {
+    let mut items = Vec::new();
+    let mut push = |section, name: &str, change, breaking, message, old_id, new_id| {
+        items.push(AbiDiffItem {
+            section,
+            name: name.to_owned(),
+            change,
+            breaking,
+            message,
+            old_id,
+            new_id,
+        })
+    };
+
+    if old.abi_version != new.abi_version {
+        push(
+            AbiSection::Version,
+            "version",
+            AbiChangeKind::Changed,
+            old.abi_version.major != new.abi_version.major,
+            format!(
+                "ABI version changed from {}.{} to {}.{}",
+                old.abi_version.major,
+                old.abi_version.minor,
+                new.abi_version.major,
+                new.abi_version.minor
+            ),
+            None,
+            None,
+        );
+    }
+
+    if let Some(message) = diff_params("headers", &old.header, &new.header, true) {
+        push(
+            AbiSection::Header,
+            "header",
+            AbiChangeKind::Changed,
+            true,
+            format!("{message}, external messages built with the old ABI will be rejected"),
+            None,
+            None,
+        );
+    }
+
+    for (name, function) in old.functions.iter().collect::<BTreeMap<_, _>>() {
+        let id = Some(function.input_id);
+        let other = match new.functions.get(name) {
+            Some(other) => other,
+            None => {
+                push(
+                    AbiSection::Function,
+                    name,
+                    AbiChangeKind::Removed,
+                    true,
+                    format!("function `{name}` was removed"),
+                    id,
+                    None,
+                );
+                continue;
+            }
+        };
+        let new_id = Some(other.input_id);
+
+        if function.input_id != other.input_id {
+            push(
+                AbiSection::Function,
+                name,
+                AbiChangeKind::Changed,
+                true,
+                format!(
+                    "function `{name}` id changed from 0x{:08x} to 0x{:08x}",
+                    function.input_id, other.input_id
+                ),
+                id,
+                new_id,
+            );
+        }
+        if let Some(message) = diff_params("inputs", &function.inputs, &other.inputs, true) {
+            push(
+                AbiSection::Function,
+                name,
+                AbiChangeKind::Changed,
+                true,
+                format!("function `{name}` {message}"),
+                id,
+                new_id,
+            );
+        } else if !same_names(&function.inputs, &other.inputs) {
+            push(
+                AbiSection::Function,
+                name,
+                AbiChangeKind::Changed,
+                false,
+                format!("function `{name}` inputs were renamed"),
+                id,
+                new_id,
+            );
+        }
+        if let Some(message) = diff_params("outputs", &function.outputs, &other.outputs, false) {
+            let breaking = !is_prefix(&function.outputs, &other.outputs);
+            push(
+                AbiSection::Function,
+                name,
+                AbiChangeKind::Changed,
+                breaking,
+                format!("function `{name}` {message}"),
+                id,
+                new_id,
+            );
+        } else if !same_names(&function.outputs, &other.outputs) {
+            push(
+                AbiSection::Function,
+                name,
+                AbiChangeKind::Changed,
+                false,
+                format!("function `{name}` outputs were renamed"),
+                id,
+                new_id,
+            );
+        }
+    }
+    for (name, function) in new.functions.iter().collect::<BTreeMap<_, _>>() {
+        if !old.functions.contains_key(name) {
+            push(
+                AbiSection::Function,
+                name,
+                AbiChangeKind::Added,
+                false,
+                format!("function `{name}` was added"),
+                None,
+                Some(function.input_id),
+            );
+        }
+    }
+
+    for (name, event) in old.events.iter().collect::<BTreeMap<_, _>>() {
+        let id = Some(event.id);
+        let other = match new.events.get(name) {
+            Some(other) => other,
+            None => {
+                push(
+                    AbiSection::Event,
+                    name,
+                    AbiChangeKind::Removed,
+                    true,
+                    format!("event `{name}` was removed"),
+                    id,
+                    None,
+                );
+                continue;
+            }
+        };
+        let new_id = Some(other.id);
+
+        if event.id != other.id {
+            push(
+                AbiSection::Event,
+                name,
+                AbiChangeKind::Changed,
+                true,
+                format!(
+                    "event `{name}` id changed from 0x{:08x} to 0x{:08x}",
+                    event.id, other.id
+                ),
+                id,
+                new_id,
+            );
+        }
+        if let Some(message) = diff_params("inputs", &event.inputs, &other.inputs, false) {
+            push(
+                AbiSection::Event,
+                name,
+                AbiChangeKind::Changed,
+                !is_prefix(&event.inputs, &other.inputs),
+                format!("event `{name}` {message}"),
+                id,
+                new_id,
+            );
+        }
+    }
+    for (name, event) in new.events.iter().collect::<BTreeMap<_, _>>() {
+        if !old.events.contains_key(name) {
+            push(
+                AbiSection::Event,
+                name,
+                AbiChangeKind::Added,
+                false,
+                format!("event `{name}` was added"),
+                None,
+                Some(event.id),
+            );
+        }
+    }
+
+    // Storage layout must be preserved for `setcode` upgrades without data migration
+    for (i, field) in old.fields.iter().enumerate() {
+        match new.fields.get(i) {
+            None => push(
+                AbiSection::Field,
+                &field.name,
+                AbiChangeKind::Removed,
+                true,
+                format!("field `{}` was removed from the storage layout", field.name),
+                None,
+                None,
+            ),
+            Some(other) if other.kind != field.kind => push(
+                AbiSection::Field,
+                &field.name,
+                AbiChangeKind::Changed,
+                true,
+                format!(
+                    "field #{i} changed from `{}: {}` to `{}: {}`",
+                    field.name,
+                    field.kind.type_signature(),
+                    other.name,
+                    other.kind.type_signature()
+                ),
+                None,
+                None,
+            ),
+            Some(other) if other.name != field.name => push(
+                AbiSection::Field,
+                &field.name,
+                AbiChangeKind::Changed,
+                false,
+                format!(
+                    "field #{i} was renamed from `{}` to `{}`",
+                    field.name, other.name
+                ),
+                None,
+                None,
+            ),
+            Some(_) => {}
+        }
+    }
+    for field in new.fields.iter().skip(old.fields.len()) {
+        push(
+            AbiSection::Field,
+            &field.name,
+            AbiChangeKind::Added,
+            true,
+            format!(
+                "field `{}` was added, existing data must be migrated in `onCodeUpgrade`",
+                field.name
+            ),
+            None,
+            None,
+        );
+    }
+
+    items
+}
+
+/// Describes the difference between two parameter lists by types only.
+fn diff_params(what: &str, old: &[Param], new: &[Param], strict: bool) -> Option<String> {
+    if old.len() == new.len() && old.iter().zip(new).all(|(a, b)| a.kind == b.kind) {
+        return None;
+    }
+    let signature = |params: &[Param]| {
+        params
+            .iter()
+            .map(|param| param.kind.type_signature())
+            .collect::<Vec<_>>()
+            .join(",")
+    };
+    let verb = if !strict && is_prefix(old, new) {
+        "were extended"
+    } else {
+        "changed"
+    };
+    Some(format!(
+        "{what} {verb} from ({}) to ({})",
+        signature(old),
+        signature(new)
+    ))
+}
+
+fn is_prefix(old: &[Param], new: &[Param]) -> bool {
+    old.len() <= new.len() && old.iter().zip(new).all(|(a, b)| a.kind == b.kind)
+}
+
+fn same_names(old: &[Param], new: &[Param]) -> bool {
+    old.iter().zip(new).all(|(a, b)| a.name == b.name)
+}
+
 #[wasm_bindgen(js_name = "computeTip6InterfaceId")]
 pub fn compute_tip6_interface_id(method_ids: &[u32]) -> u32 {
     let mut result = 0;
@@ -54,3 +887,227 @@ pub fn compute_tip6_interface_id(method_ids: &[u32]) -> u32 {
     }
     result
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn make_abi(functions: Value, events: Value, fields: Value) -> Value {
+        json!({
+            "ABI version": 2,
+            "version": "2.3",
+            "header": ["time"],
+            "functions": functions,
+            "events": events,
+            "fields": fields,
+        })
+    }
+
+    fn load(abi: &Value) -> ton_abi::Contract {
+        ton_abi::Contract::load(abi.to_string().as_bytes()).unwrap()
+    }
+
+    fn function(name: &str, inputs: Value, outputs: Value) -> Value {
+        json!({ "name": name, "id": "0x00000001", "inputs": inputs, "outputs": outputs })
+    }
+
+    fn diff_functions(old: Value, new: Value) -> Vec<AbiDiffItem> {
+        diff_abi(
+            &load(&make_abi(json!([old]), json!([]), json!([]))),
+            &load(&make_abi(json!([new]), json!([]), json!([]))),
+        )
+    }
+
+    #[test]
+    fn prefix_params() {
+        let params = |kinds: &[ParamType]| {
+            kinds
+                .iter()
+                .enumerate()
+                .map(|(i, kind)| Param::new(&format!("value{i}"), kind.clone()))
+                .collect::<Vec<_>>()
+        };
+        let old = params(&[ParamType::Uint(32)]);
+        let extended = params(&[ParamType::Uint(32), ParamType::Bool]);
+
+        assert!(is_prefix(&old, &old));
+        assert!(is_prefix(&old, &extended));
+        assert!(!is_prefix(&extended, &old));
+        assert!(!is_prefix(&old, &params(&[ParamType::Uint(64)])));
+
+        assert_eq!(diff_params("outputs", &old, &old, false), None);
+        assert_eq!(
+            diff_params("outputs", &old, &extended, false).unwrap(),
+            "outputs were extended from (uint32) to (uint32,bool)"
+        );
+        assert_eq!(
+            diff_params("inputs", &old, &extended, true).unwrap(),
+            "inputs changed from (uint32) to (uint32,bool)"
+        );
+    }
+
+    #[test]
+    fn function_changes() {
+        let a = json!({ "name": "a", "type": "uint32" });
+        let b = json!({ "name": "b", "type": "bool" });
+
+        // Appended outputs can still be decoded by old clients
+        let items = diff_functions(
+            function("get", json!([]), json!([a])),
+            function("get", json!([]), json!([a, b])),
+        );
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].change, AbiChangeKind::Changed);
+        assert!(!items[0].breaking);
+
+        // Removed outputs can't
+        let items = diff_functions(
+            function("get", json!([]), json!([a, b])),
+            function("get", json!([]), json!([a])),
+        );
+        assert_eq!(items.len(), 1);
+        assert!(items[0].breaking);
+
+        // Any input change is breaking
+        let items = diff_functions(
+            function("set", json!([a]), json!([])),
+            function("set", json!([a, b]), json!([])),
+        );
+        assert_eq!(items.len(), 1);
+        assert!(items[0].breaking);
+
+        // Renames are not
+        let items = diff_functions(
+            function("set", json!([a]), json!([])),
+            function("set", json!([{ "name": "c", "type": "uint32" }]), json!([])),
+        );
+        assert_eq!(items.len(), 1);
+        assert!(!items[0].breaking);
+
+        // Signature changes without explicit ids change the function id
+        let items = diff_functions(
+            json!({ "name": "get", "inputs": [], "outputs": [a] }),
+            json!({ "name": "get", "inputs": [], "outputs": [a, b] }),
+        );
+        assert_eq!(items.len(), 2);
+        assert!(items[0].breaking && items[0].old_id != items[0].new_id);
+        assert!(!items[1].breaking);
+
+        let items = diff_functions(
+            function("get", json!([]), json!([])),
+            json!({ "name": "other", "inputs": [], "outputs": [] }),
+        );
+        assert_eq!(items.len(), 2);
+        assert_eq!(
+            (items[0].change, items[0].breaking),
+            (AbiChangeKind::Removed, true)
+        );
+        assert_eq!(
+            (items[1].change, items[1].breaking),
+            (AbiChangeKind::Added, false)
+        );
+    }
+
+    #[test]
+    fn field_changes() {
+        let pubkey = json!({ "name": "_pubkey", "type": "uint256" });
+        let a = json!({ "name": "a", "type": "uint32" });
+        let b = json!({ "name": "b", "type": "bool" });
+        let diff_fields = |old: Value, new: Value| {
+            diff_abi(
+                &load(&make_abi(json!([]), json!([]), old)),
+                &load(&make_abi(json!([]), json!([]), new)),
+            )
+        };
+
+        // Reordered fields change the layout
+        let items = diff_fields(json!([pubkey, a, b]), json!([pubkey, b, a]));
+        assert_eq!(items.len(), 2);
+        assert!(items.iter().all(|item| item.breaking));
+
+        let items = diff_fields(json!([pubkey, a]), json!([pubkey, a, b]));
+        assert_eq!(items.len(), 1);
+        assert_eq!(
+            (items[0].change, items[0].breaking),
+            (AbiChangeKind::Added, true)
+        );
+
+        let items = diff_fields(
+            json!([pubkey, a]),
+            json!([pubkey, { "name": "c", "type": "uint32" }]),
+        );
+        assert_eq!(items.len(), 1);
+        assert!(!items[0].breaking);
+    }
+
+    #[test]
+    fn event_changes() {
+        let a = json!({ "name": "a", "type": "uint32" });
+        let b = json!({ "name": "b", "type": "bool" });
+        let event =
+            |inputs: Value| json!([{ "name": "Event", "id": "0x00000002", "inputs": inputs }]);
+        let diff_events = |old: Value, new: Value| {
+            diff_abi(
+                &load(&make_abi(json!([]), old, json!([]))),
+                &load(&make_abi(json!([]), new, json!([]))),
+            )
+        };
+
+        let items = diff_events(event(json!([a])), event(json!([a, b])));
+        assert_eq!(items.len(), 1);
+        assert!(!items[0].breaking);
+
+        let items = diff_events(event(json!([a, b])), event(json!([b, a])));
+        assert_eq!(items.len(), 1);
+        assert!(items[0].breaking);
+    }
+
+    fn tip6_findings(functions: Value) -> Vec<&'static str> {
+        let abi = make_abi(functions, json!([]), json!([]));
+        lint_abi(&load(&abi), &abi)
+            .into_iter()
+            .map(|finding| finding.code)
+            .filter(|code| code.starts_with("tip6"))
+            .collect()
+    }
+
+    #[test]
+    fn tip6_lint() {
+        let answer_id = json!({ "name": "answerId", "type": "uint32" });
+        let get_json = json!({
+            "name": "getJson",
+            "inputs": [answer_id],
+            "outputs": [{ "name": "json", "type": "string" }]
+        });
+        let supports_interface = |interface_id: &str| {
+            json!({
+                "name": "supportsInterface",
+                "inputs": [answer_id, { "name": "interfaceID", "type": interface_id }],
+                "outputs": [{ "name": "value0", "type": "bool" }]
+            })
+        };
+        let index_info = json!({
+            "name": "getInfo",
+            "inputs": [answer_id],
+            "outputs": [
+                { "name": "collection", "type": "address" },
+                { "name": "owner", "type": "address" },
+                { "name": "nft", "type": "address" }
+            ]
+        });
+
+        assert_eq!(tip6_findings(json!([get_json])), ["tip6-missing"]);
+        assert!(tip6_findings(json!([get_json, supports_interface("uint32")])).is_empty());
+        assert_eq!(
+            tip6_findings(json!([get_json, supports_interface("uint256")])),
+            ["tip6-signature"]
+        );
+        assert_eq!(
+            tip6_findings(json!([index_info, supports_interface("uint32")])),
+            ["tip6-partial"]
+        );
+        assert!(tip6_findings(json!([])).is_empty());
+    }
+}
@@ -1,6 +1,12 @@
+pub mod address;
+pub mod boc;
 pub mod executor;
 pub mod microwave;
+pub mod overlay;
 pub mod serializer;
+pub mod slice;
+pub mod state;
+pub mod tip6;
 pub mod visualizer;
 pub mod deserializer;
 pub mod codegen;
@@ -64,22 +64,1100 @@ pub fn parse_state_init(account_boc: &str) -> Result<Option<String>, JsValue> {
 pub fn parse_account_stuff(boc: &str) -> Result<ton_block::AccountStuff, JsValue> {
     let bytes = base64::decode(boc.trim()).handle_error()?;
     ton_types::deserialize_tree_of_cells(&mut bytes.as_slice())
-        .and_then(|cell| {
-            let slice = &mut ton_types::SliceData::load_cell(cell)?;
-            Ok(ton_block::AccountStuff {
-                addr: Deserializable::construct_from(slice)?,
-                storage_stat: Deserializable::construct_from(slice)?,
-                storage: ton_block::AccountStorage {
-                    last_trans_lt: Deserializable::construct_from(slice)?,
-                    balance: Deserializable::construct_from(slice)?,
-                    state: Deserializable::construct_from(slice)?,
-                    init_code_hash: if slice.remaining_bits() > 0 {
-                        ton_types::UInt256::read_maybe_from(slice)?
-                    } else {
-                        None
-                    },
-                },
-            })
+        .and_then(read_account_stuff)
+        .handle_error()
+}
+
+fn read_account_stuff(cell: ton_types::Cell) -> anyhow::Result<ton_block::AccountStuff> {
+    let slice = &mut ton_types::SliceData::load_cell(cell)?;
+    Ok(ton_block::AccountStuff {
+        addr: Deserializable::construct_from(slice)?,
+        storage_stat: Deserializable::construct_from(slice)?,
+        storage: ton_block::AccountStorage {
+            last_trans_lt: Deserializable::construct_from(slice)?,
+            balance: Deserializable::construct_from(slice)?,
+            state: Deserializable::construct_from(slice)?,
+            init_code_hash: if slice.remaining_bits() > 0 {
+                ton_types::UInt256::read_maybe_from(slice)?
+            } else {
+                None
+            },
+        },
+    })
+}
+
+/// Reads an `Account`, `ShardAccount` or bare `AccountStuff` cell.
+pub(crate) fn read_any_account(cell: ton_types::Cell) -> anyhow::Result<ton_block::Account> {
+    if is_shard_account(&cell) {
+        return ton_block::ShardAccount::construct_from_cell(cell)?.read_account();
+    }
+    match ton_block::Account::construct_from_cell(cell.clone()) {
+        Ok(account) => Ok(account),
+        Err(_) => read_account_stuff(cell).map(ton_block::Account::Account),
+    }
+}
+
+fn parse_any_account_stuff(boc: &str) -> anyhow::Result<ton_block::AccountStuff> {
+    match read_any_account(crate::boc::parse_cell(boc)?)? {
+        ton_block::Account::Account(account_stuff) => Ok(account_stuff),
+        ton_block::Account::AccountNone => Err(anyhow::anyhow!("Account doesn't exist")),
+    }
+}
+
+fn is_shard_account(cell: &ton_types::Cell) -> bool {
+    cell.references_count() == 1 && cell.bit_length() == 256 + 64
+}
+
+#[wasm_bindgen(typescript_custom_section)]
+const ACCOUNT_INFO: &str = r#"
+export type AccountInfo = {
+    kind: 'shardAccount' | 'account' | 'accountStuff';
+    status: 'nonexist' | 'uninit' | 'active' | 'frozen';
+    address: string | undefined;
+    balance: string;
+    extraCurrencies: { [id: number]: string };
+    storage: {
+        cells: string;
+        bits: string;
+        publicCells: string;
+        lastPaid: number;
+        duePayment: string | undefined;
+    } | undefined;
+    lastTransLt: string | undefined;
+    codeHash: string | undefined;
+    dataHash: string | undefined;
+    splitDepth: number | undefined;
+    special: { tick: boolean; tock: boolean } | undefined;
+    libraries: { hash: string; public: boolean }[];
+    stateInitHash: string | undefined;
+    initCodeHash: string | undefined;
+    lastTransaction: { lt: string; hash: string } | undefined;
+}
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "AccountInfo")]
+    pub type AccountInfo;
+}
+
+/// Returns a summary of an `Account`, `ShardAccount` or bare `AccountStuff` BOC.
+#[wasm_bindgen(js_name = "inspectAccount")]
+pub fn inspect_account(boc: &str) -> Result<AccountInfo, JsValue> {
+    let cell = crate::boc::parse_cell(boc).handle_error()?;
+
+    let mut last_transaction = None;
+    let (kind, account) = if is_shard_account(&cell) {
+        let shard_account = ton_block::ShardAccount::construct_from_cell(cell).handle_error()?;
+        last_transaction = Some(
+            ObjectBuilder::new()
+                .set("lt", shard_account.last_trans_lt().to_string())
+                .set("hash", shard_account.last_trans_hash().to_hex_string())
+                .build(),
+        );
+        let account = shard_account.read_account().handle_error()?;
+        ("shardAccount", account)
+    } else {
+        match ton_block::Account::construct_from_cell(cell.clone()) {
+            Ok(account) => ("account", account),
+            Err(_) => (
+                "accountStuff",
+                ton_block::Account::Account(read_account_stuff(cell).handle_error()?),
+            ),
+        }
+    };
+
+    let mut info = ObjectBuilder::new()
+        .set("kind", kind)
+        .set("lastTransaction", last_transaction);
+
+    let account_stuff = match account {
+        ton_block::Account::Account(account_stuff) => account_stuff,
+        _ => {
+            return Ok(info
+                .set("status", "nonexist")
+                .set("balance", "0")
+                .set("extraCurrencies", js_sys::Object::new())
+                .set("libraries", js_sys::Array::new())
+                .build()
+                .unchecked_into())
+        }
+    };
+
+    let balance = &account_stuff.storage.balance;
+    let extra_currencies = js_sys::Object::new();
+    balance
+        .other
+        .iterate_with_keys(|id: u32, value| {
+            js_sys::Reflect::set(
+                &extra_currencies,
+                &JsValue::from(id),
+                &JsValue::from(value.to_string()),
+            )
+            .ok();
+            Ok(true)
         })
+        .handle_error()?;
+
+    let storage_stat = &account_stuff.storage_stat;
+    info = info
+        .set("address", account_stuff.addr.to_string())
+        .set("balance", balance.grams.to_string())
+        .set("extraCurrencies", extra_currencies)
+        .set(
+            "storage",
+            ObjectBuilder::new()
+                .set("cells", storage_stat.used.cells().to_string())
+                .set("bits", storage_stat.used.bits().to_string())
+                .set("publicCells", storage_stat.used.public_cells().to_string())
+                .set("lastPaid", storage_stat.last_paid)
+                .set(
+                    "duePayment",
+                    storage_stat.due_payment.as_ref().map(ToString::to_string),
+                )
+                .build(),
+        )
+        .set(
+            "lastTransLt",
+            account_stuff.storage.last_trans_lt.to_string(),
+        )
+        .set(
+            "initCodeHash",
+            account_stuff
+                .storage
+                .init_code_hash
+                .as_ref()
+                .map(|hash| hash.to_hex_string()),
+        );
+
+    let libraries = js_sys::Array::new();
+    info = match &account_stuff.storage.state {
+        ton_block::AccountState::AccountUninit => info.set("status", "uninit"),
+        ton_block::AccountState::AccountFrozen { state_init_hash } => info
+            .set("status", "frozen")
+            .set("stateInitHash", state_init_hash.to_hex_string()),
+        ton_block::AccountState::AccountActive { state_init } => {
+            state_init
+                .library
+                .iterate_with_keys(|hash: ton_types::UInt256, lib: ton_block::SimpleLib| {
+                    libraries.push(
+                        &ObjectBuilder::new()
+                            .set("hash", hash.to_hex_string())
+                            .set("public", lib.is_public_library())
+                            .build(),
+                    );
+                    Ok(true)
+                })
+                .handle_error()?;
+
+            info.set("status", "active")
+                .set(
+                    "codeHash",
+                    state_init
+                        .code
+                        .as_ref()
+                        .map(|code| code.repr_hash().to_hex_string()),
+                )
+                .set(
+                    "dataHash",
+                    state_init
+                        .data
+                        .as_ref()
+                        .map(|data| data.repr_hash().to_hex_string()),
+                )
+                .set(
+                    "splitDepth",
+                    state_init.split_depth.as_ref().map(|depth| depth.as_u32()),
+                )
+                .set(
+                    "special",
+                    state_init.special.as_ref().map(|special| {
+                        ObjectBuilder::new()
+                            .set("tick", special.tick)
+                            .set("tock", special.tock)
+                            .build()
+                    }),
+                )
+        }
+    };
+
+    Ok(info.set("libraries", libraries).build().unchecked_into())
+}
+
+#[wasm_bindgen(typescript_custom_section)]
+const STORAGE_FORECAST: &str = r#"
+export type StorageForecast = {
+    lastPaid: number;
+    storageFee: string;
+    duePayment: string;
+    balance: string;
+    debt: string;
+    freezeAt: number | undefined;
+    deleteAt: number | undefined;
+}
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "StorageForecast")]
+    pub type StorageForecast;
+}
+
+/// Computes the storage fee accrued by the account at the specified timestamp
+/// and the times when it will be frozen and deleted under the current storage prices.
+///
+/// The account can be an `Account`, `ShardAccount` or bare `AccountStuff` BOC.
+///
+/// The freeze and deletion times assume that a transaction happens right at that moment,
+/// the deletion time of an active account is computed for its frozen state.
+#[wasm_bindgen(js_name = "computeStorageForecast")]
+pub fn compute_storage_forecast(
+    account_boc: &str,
+    config_boc: &str,
+    at: u32,
+) -> Result<StorageForecast, JsValue> {
+    let account_stuff = parse_any_account_stuff(account_boc).handle_error()?;
+    let config = ton_block::ConfigParams::construct_from_cell(
+        crate::boc::parse_cell(config_boc).handle_error()?,
+    )
+    .handle_error()?;
+
+    let is_masterchain = account_stuff.addr.workchain_id() == ton_block::MASTERCHAIN_ID;
+    let calculator = StorageFeeCalculator::new(&config, is_masterchain).handle_error()?;
+    let gas_prices = config.gas_prices(is_masterchain).handle_error()?;
+    let (freeze_at, delete_at) = find_storage_deadlines(
+        &account_stuff,
+        &calculator,
+        gas_prices.freeze_due_limit as u128,
+        gas_prices.delete_due_limit as u128,
+    )
+    .handle_error()?;
+
+    let storage_stat = &account_stuff.storage_stat;
+    let last_paid = storage_stat.last_paid;
+    let due_payment = storage_stat
+        .due_payment
+        .as_ref()
+        .map(|grams| grams.as_u128())
+        .unwrap_or_default();
+    let balance = account_stuff.storage.balance.grams.as_u128();
+
+    let storage_fee = calculator.compute(&storage_stat.used, last_paid, at);
+    let total = storage_fee + due_payment;
+
+    Ok(ObjectBuilder::new()
+        .set("lastPaid", last_paid)
+        .set("storageFee", storage_fee.to_string())
+        .set("duePayment", due_payment.to_string())
+        .set("balance", balance.saturating_sub(total).to_string())
+        .set("debt", total.saturating_sub(balance).to_string())
+        .set("freezeAt", freeze_at)
+        .set("deleteAt", delete_at)
+        .build()
+        .unchecked_into())
+}
+
+/// Finds the times when the account will be frozen and deleted.
+fn find_storage_deadlines(
+    account_stuff: &ton_block::AccountStuff,
+    calculator: &StorageFeeCalculator,
+    freeze_due_limit: u128,
+    delete_due_limit: u128,
+) -> anyhow::Result<(Option<u32>, Option<u32>)> {
+    let storage_stat = &account_stuff.storage_stat;
+    let last_paid = storage_stat.last_paid;
+    let due_payment = storage_stat
+        .due_payment
+        .as_ref()
+        .map(|grams| grams.as_u128())
+        .unwrap_or_default();
+    let balance = account_stuff.storage.balance.grams.as_u128();
+    let total_due = |used: &ton_block::StorageUsed, now: u32| {
+        calculator.compute(used, last_paid, now) + due_payment
+    };
+
+    Ok(match &account_stuff.storage.state {
+        ton_block::AccountState::AccountActive { .. } => {
+            let freeze_at = find_time(last_paid, |now| {
+                total_due(&storage_stat.used, now) > balance + freeze_due_limit
+            });
+
+            let delete_at = match freeze_at {
+                Some(freeze_at) => {
+                    let debt = total_due(&storage_stat.used, freeze_at) - balance;
+
+                    let mut frozen = ton_block::Account::Account(account_stuff.clone());
+                    frozen.try_freeze()?;
+                    frozen.update_storage_stat()?;
+                    let frozen_used = match frozen.storage_info() {
+                        Some(info) => info.used.clone(),
+                        None => storage_stat.used.clone(),
+                    };
+
+                    find_time(freeze_at, |now| {
+                        debt + calculator.compute(&frozen_used, freeze_at, now) > delete_due_limit
+                    })
+                }
+                None => None,
+            };
+
+            (freeze_at, delete_at)
+        }
+        _ => (
+            None,
+            find_time(last_paid, |now| {
+                total_due(&storage_stat.used, now) > balance + delete_due_limit
+            }),
+        ),
+    })
+}
+
+pub(crate) struct StorageFeeCalculator {
+    prices: Vec<ton_block::StoragePrices>,
+    is_masterchain: bool,
+}
+
+impl StorageFeeCalculator {
+    pub(crate) fn new(
+        config: &ton_block::ConfigParams,
+        is_masterchain: bool,
+    ) -> anyhow::Result<Self> {
+        let storage_prices = config.storage_prices()?;
+        let prices = (0..storage_prices.len()? as u32)
+            .map(|index| storage_prices.get(index))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        Ok(Self {
+            prices,
+            is_masterchain,
+        })
+    }
+
+    /// Computes the storage fee for the period between `last_paid` and `now`.
+    fn compute(&self, used: &ton_block::StorageUsed, last_paid: u32, now: u32) -> u128 {
+        self.compute_for(used.cells() as u128, used.bits() as u128, last_paid, now)
+    }
+
+    pub(crate) fn compute_for(
+        &self,
+        cells: u128,
+        bits: u128,
+        last_paid: u32,
+        mut now: u32,
+    ) -> u128 {
+        if now <= last_paid || last_paid == 0 {
+            return 0;
+        }
+
+        let mut fee = 0u128;
+        for prices in self.prices.iter().rev() {
+            if now <= prices.utime_since {
+                continue;
+            }
+
+            let period = (now - std::cmp::max(prices.utime_since, last_paid)) as u128;
+            let (bit_price, cell_price) = if self.is_masterchain {
+                (prices.mc_bit_price_ps, prices.mc_cell_price_ps)
+            } else {
+                (prices.bit_price_ps, prices.cell_price_ps)
+            };
+            fee += (bits * bit_price as u128 + cells * cell_price as u128) * period;
+
+            now = prices.utime_since;
+            if now <= last_paid {
+                break;
+            }
+        }
+
+        (fee + 0xffff) >> 16
+    }
+
+    /// Computes the storage fee for the `period` starting at `now` under the prices
+    /// in effect at `now`.
+    pub(crate) fn compute_ahead(&self, cells: u128, bits: u128, now: u32, period: u32) -> u128 {
+        let prices = match self
+            .prices
+            .iter()
+            .rev()
+            .find(|prices| prices.utime_since <= now)
+        {
+            Some(prices) => prices,
+            None => return 0,
+        };
+
+        let (bit_price, cell_price) = if self.is_masterchain {
+            (prices.mc_bit_price_ps, prices.mc_cell_price_ps)
+        } else {
+            (prices.bit_price_ps, prices.cell_price_ps)
+        };
+        let fee = (bits * bit_price as u128 + cells * cell_price as u128) * period as u128;
+        (fee + 0xffff) >> 16
+    }
+}
+
+/// Finds the earliest timestamp starting from `from` at which the monotonic predicate holds.
+fn find_time(from: u32, predicate: impl Fn(u32) -> bool) -> Option<u32> {
+    if !predicate(u32::MAX) {
+        return None;
+    }
+
+    let (mut lo, mut hi) = (from, u32::MAX);
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        if predicate(mid) {
+            hi = mid;
+        } else {
+            lo = mid + 1;
+        }
+    }
+    Some(lo)
+}
+
+#[wasm_bindgen(typescript_custom_section)]
+const FROZEN_STATE_VERIFICATION: &str = r#"
+export type StateInitField = 'code' | 'data' | 'library' | 'special' | 'splitDepth';
+
+export type FrozenStateVerification = {
+    matches: boolean;
+    expectedHash: string;
+    actualHash: string;
+    differences: StateInitField[];
+    conclusive: boolean;
+}
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "FrozenStateVerification")]
+    pub type FrozenStateVerification;
+}
+
+/// Checks that the candidate state matches the `state_init_hash` of the frozen account.
+///
+/// The account is an `Account`, `ShardAccount` or bare `AccountStuff` BOC. The candidate
+/// and the optional pre-freeze state are StateInit BOCs or active accounts in any of these
+/// formats.
+///
+/// When the pre-freeze state matches the frozen hash, the candidate fields are compared
+/// with it. Otherwise only the hash of the frozen state is known, so the differences are
+/// found by resetting the optional fields of the candidate. Only verified differences are
+/// reported: when none of the resets helps, the list is empty and the result is marked
+/// as not conclusive.
+#[wasm_bindgen(js_name = "verifyFrozenState")]
+pub fn verify_frozen_state(
+    account_boc: &str,
+    candidate_boc: &str,
+    previous_boc: Option<String>,
+) -> Result<FrozenStateVerification, JsValue> {
+    let expected_hash = frozen_state_hash(account_boc).handle_error()?;
+    let candidate = parse_candidate_state_init(candidate_boc).handle_error()?;
+    let previous = previous_boc
+        .as_deref()
+        .map(parse_candidate_state_init)
+        .transpose()
+        .handle_error()?;
+
+    let verification =
+        check_frozen_state(&expected_hash, &candidate, previous.as_ref()).handle_error()?;
+
+    Ok(ObjectBuilder::new()
+        .set("matches", verification.actual_hash == expected_hash)
+        .set("expectedHash", expected_hash.to_hex_string())
+        .set("actualHash", verification.actual_hash.to_hex_string())
+        .set(
+            "differences",
+            verification
+                .differences
+                .into_iter()
+                .map(JsValue::from_str)
+                .collect::<js_sys::Array>(),
+        )
+        .set("conclusive", verification.conclusive)
+        .build()
+        .unchecked_into())
+}
+
+/// Builds an internal message which restores the frozen account with the specified state.
+///
+/// The message is non-bounceable and has no source address, so it can be sent as is
+/// by any contract (e.g. the microwave) which attaches the value.
+#[wasm_bindgen(js_name = "buildUnfreezeMessage")]
+pub fn build_unfreeze_message(
+    account_boc: &str,
+    candidate_boc: &str,
+    value: &str,
+) -> Result<String, JsValue> {
+    let account_stuff = parse_any_account_stuff(account_boc).handle_error()?;
+    let expected_hash = match &account_stuff.storage.state {
+        ton_block::AccountState::AccountFrozen { state_init_hash } => state_init_hash.clone(),
+        _ => return Err("Account is not frozen").handle_error(),
+    };
+
+    let state_init = parse_candidate_state_init(candidate_boc).handle_error()?;
+    if state_init.serialize().handle_error()?.repr_hash() != expected_hash {
+        return Err("State doesn't match the frozen state hash").handle_error();
+    }
+
+    let value = value.trim().parse::<u128>().handle_error()?;
+    let header = ton_block::InternalMessageHeader {
+        ihr_disabled: true,
+        bounce: false,
+        dst: account_stuff.addr,
+        value: ton_block::CurrencyCollection::from_grams(
+            ton_block::Grams::new(value).handle_error()?,
+        ),
+        ..Default::default()
+    };
+
+    let mut message = ton_block::Message::with_int_header(header);
+    message.set_state_init(state_init);
+
+    ton_types::serialize_toc(&message.serialize().handle_error()?)
+        .map(base64::encode)
+        .handle_error()
+}
+
+fn frozen_state_hash(account_boc: &str) -> anyhow::Result<ton_types::UInt256> {
+    match parse_any_account_stuff(account_boc)?.storage.state {
+        ton_block::AccountState::AccountFrozen { state_init_hash } => Ok(state_init_hash),
+        _ => Err(anyhow::anyhow!("Account is not frozen")),
+    }
+}
+
+/// Reads a StateInit BOC or the state of an active account.
+pub(crate) fn parse_candidate_state_init(boc: &str) -> anyhow::Result<ton_block::StateInit> {
+    let cell = crate::boc::parse_cell(boc)?;
+    // StateInit has at most 12 data bits, while accounts start with an address
+    if cell.bit_length() <= 12 {
+        return ton_block::StateInit::construct_from_cell(cell);
+    }
+    match read_any_account(cell)? {
+        ton_block::Account::Account(account) => match account.storage.state {
+            ton_block::AccountState::AccountActive { state_init } => Ok(state_init),
+            _ => Err(anyhow::anyhow!("Candidate account is not active")),
+        },
+        ton_block::Account::AccountNone => Err(anyhow::anyhow!("Candidate account doesn't exist")),
+    }
+}
+
+struct FrozenStateCheck {
+    actual_hash: ton_types::UInt256,
+    differences: Vec<&'static str>,
+    conclusive: bool,
+}
+
+fn check_frozen_state(
+    expected_hash: &ton_types::UInt256,
+    candidate: &ton_block::StateInit,
+    previous: Option<&ton_block::StateInit>,
+) -> anyhow::Result<FrozenStateCheck> {
+    let actual_hash = candidate.serialize()?.repr_hash();
+
+    let (differences, conclusive) = if actual_hash == *expected_hash {
+        (Vec::new(), true)
+    } else {
+        match previous {
+            Some(previous) if previous.serialize()?.repr_hash() == *expected_hash => {
+                (compare_state_inits(candidate, previous)?, true)
+            }
+            _ => find_state_init_differences(candidate, expected_hash)?,
+        }
+    };
+
+    Ok(FrozenStateCheck {
+        actual_hash,
+        differences,
+        conclusive,
+    })
+}
+
+fn compare_state_inits(
+    candidate: &ton_block::StateInit,
+    previous: &ton_block::StateInit,
+) -> anyhow::Result<Vec<&'static str>> {
+    let hash = |cell: &Option<ton_types::Cell>| cell.as_ref().map(|cell| cell.repr_hash());
+
+    let mut differences = Vec::new();
+    if hash(&candidate.code) != hash(&previous.code) {
+        differences.push("code");
+    }
+    if hash(&candidate.data) != hash(&previous.data) {
+        differences.push("data");
+    }
+    if candidate.library.serialize()?.repr_hash() != previous.library.serialize()?.repr_hash() {
+        differences.push("library");
+    }
+    if candidate.special != previous.special {
+        differences.push("special");
+    }
+    if candidate.split_depth != previous.split_depth {
+        differences.push("splitDepth");
+    }
+    Ok(differences)
+}
+
+fn find_state_init_differences(
+    candidate: &ton_block::StateInit,
+    expected_hash: &ton_types::UInt256,
+) -> anyhow::Result<(Vec<&'static str>, bool)> {
+    let mut fields = Vec::new();
+    if candidate.split_depth.is_some() {
+        fields.push("splitDepth");
+    }
+    if candidate.special.is_some() {
+        fields.push("special");
+    }
+    if !candidate.library.is_empty() {
+        fields.push("library");
+    }
+
+    // Try resetting every subset of the optional fields
+    for mask in 1..(1u32 << fields.len()) {
+        let mut state_init = candidate.clone();
+        let mut differences = Vec::new();
+        for (i, field) in fields.iter().enumerate() {
+            if mask & (1 << i) == 0 {
+                continue;
+            }
+            match *field {
+                "splitDepth" => state_init.split_depth = None,
+                "special" => state_init.special = None,
+                _ => state_init.library = Default::default(),
+            }
+            differences.push(*field);
+        }
+
+        if state_init.serialize()?.repr_hash() == *expected_hash {
+            return Ok((differences, true));
+        }
+    }
+
+    // Code, data or the fields themselves differ, which can't be told from the hash alone
+    Ok((Vec::new(), false))
+}
+
+const FACTORY_BOC: &str = "te6ccgEBDQEA4gACATQDAQEBwAIAQ9AAAAAACAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAACACKP8AIMEB9KQgWJL0oOBfAoog7VPZBgQBCvSkIPShBQAAAgEgDAcCAv0KCAIBIAsJALcAe1AAfhhAdMAAcAAjjsw0z/TH9MfghAnss0dErryqfgAcPhk1fpA1NHIgBDPCwUSznD6AnbPC2vMyYEAoPsAXwPtUIIQJ7LNHSBZAVUB4IECABLXGAEwIVUB2YAIBIAsLAAU8jaAANN8wIPhh0NMAAcAAkvIw4dYB0wAwwADyafI3";
+const MICROWAVE_BOC: &str = "te6ccgEBCQEA3gACATQDAQEBwAIAQ9AAAAAACAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAACACKP8AIMEB9KQgWJL0oOBfAoog7VPZBgQBCvSkIPShBQAAAQPQQAcB/gHQ0wABwADysNYB0wAwwADyvDAgxwGa7UDtUHADXwPbMAHAAI5QMAHTH4IQU8UVHyIBuZcwwADyfPI84IIQU8UVHxK68ryCEDuaygBw+wLIgBDPCwUB+kACznD6AnbPC2sB1DABzMmBAID7ACBwcFkBVQFVAtkgWQFVAeAixwIIAAzAACIiAeI=";
+
+const FACTORY_ABI: &str = r#"{
+  "ABI version": 2,
+  "version": "2.2",
+  "header": ["time", "expire"],
+  "functions": [
+    {
+      "name": "constructor",
+      "inputs": [
+        { "name": "dest", "type": "address" },
+        { "name": "state_init", "type": "cell" }
+      ],
+      "outputs": []
+    }
+  ],
+  "fields": [{ "name": "__uninitialized", "type": "bool" }],
+  "events": []
+}"#;
+
+const MICROWAVE_ABI: &str = r#"{
+  "ABI version": 2,
+  "version": "2.2",
+  "header": [],
+  "functions": [
+    {
+      "name": "deploy",
+      "inputs": [
+        { "name": "dest", "type": "address" },
+        { "name": "state_init", "type": "cell" }
+      ],
+      "outputs": []
+    }
+  ],
+  "events": []
+}"#;
+
+/// Whether the `deploy` call of the microwave is sent as a bounceable message.
+pub const MICROWAVE_DEPLOY_BOUNCE: bool = false;
+
+#[wasm_bindgen(typescript_custom_section)]
+const MICROWAVE_CONTRACTS: &str = r#"
+export type MicrowaveContracts = {
+    factoryAddress: string;
+    factoryBoc: string;
+    factoryAbi: string;
+    microwaveAddress: string;
+    microwaveBoc: string;
+    microwaveAbi: string;
+    deployBounce: boolean;
+}
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "MicrowaveContracts")]
+    pub type MicrowaveContracts;
+}
+
+/// Returns the embedded factory and microwave contracts along with their addresses.
+#[wasm_bindgen(js_name = "getMicrowaveContracts")]
+pub fn get_microwave_contracts() -> Result<MicrowaveContracts, JsValue> {
+    Ok(ObjectBuilder::new()
+        .set(
+            "factoryAddress",
+            factory_address().handle_error()?.to_string(),
+        )
+        .set("factoryBoc", FACTORY_BOC)
+        .set("factoryAbi", FACTORY_ABI)
+        .set(
+            "microwaveAddress",
+            microwave_address().handle_error()?.to_string(),
+        )
+        .set("microwaveBoc", MICROWAVE_BOC)
+        .set("microwaveAbi", MICROWAVE_ABI)
+        .set("deployBounce", MICROWAVE_DEPLOY_BOUNCE)
+        .build()
+        .unchecked_into())
+}
+
+/// Encodes the body of the internal `deploy(dest, state_init)` call of the microwave.
+#[wasm_bindgen(js_name = "encodeMicrowaveDeploy")]
+pub fn encode_microwave_deploy(dest: &str, state_init_boc: &str) -> Result<String, JsValue> {
+    let body = make_microwave_deploy_body(dest, state_init_boc).handle_error()?;
+    ton_types::serialize_toc(&body)
+        .map(base64::encode)
         .handle_error()
 }
+
+/// Builds an internal message which calls `deploy(dest, state_init)` of the microwave.
+///
+/// The source address is left empty when not specified, so the message can be used
+/// as a template for a wallet. The message is not bounceable, as the wallet message
+/// sent by the frontend.
+#[wasm_bindgen(js_name = "buildMicrowaveDeployMessage")]
+pub fn build_microwave_deploy_message(
+    dest: &str,
+    state_init_boc: &str,
+    value: &str,
+    src: Option<String>,
+) -> Result<String, JsValue> {
+    let body = make_microwave_deploy_body(dest, state_init_boc).handle_error()?;
+    let value = value.trim().parse::<u128>().handle_error()?;
+
+    let mut header = ton_block::InternalMessageHeader {
+        ihr_disabled: true,
+        bounce: MICROWAVE_DEPLOY_BOUNCE,
+        dst: microwave_address().handle_error()?,
+        value: ton_block::CurrencyCollection::from_grams(
+            ton_block::Grams::new(value).handle_error()?,
+        ),
+        ..Default::default()
+    };
+    if let Some(src) = src {
+        header.src = ton_block::MsgAddressIntOrNone::Some(
+            crate::address::parse_address(&src).handle_error()?,
+        );
+    }
+
+    let mut message = ton_block::Message::with_int_header(header);
+    message.set_body(ton_types::SliceData::load_cell(body).handle_error()?);
+
+    ton_types::serialize_toc(&message.serialize().handle_error()?)
+        .map(base64::encode)
+        .handle_error()
+}
+
+/// Encodes the body of the unsigned external `constructor(dest, state_init)` call
+/// of the factory, which makes it deploy the microwave.
+#[wasm_bindgen(js_name = "encodeFactoryDeploy")]
+pub fn encode_factory_deploy(time: f64, expire_at: u32) -> Result<String, JsValue> {
+    let body = make_factory_deploy_body(time as u64, expire_at).handle_error()?;
+    ton_types::serialize_toc(&body)
+        .map(base64::encode)
+        .handle_error()
+}
+
+/// Builds an unsigned external message which deploys the factory
+/// and makes it deploy the microwave.
+#[wasm_bindgen(js_name = "buildFactoryDeployMessage")]
+pub fn build_factory_deploy_message(time: f64, expire_at: u32) -> Result<String, JsValue> {
+    let body = make_factory_deploy_body(time as u64, expire_at).handle_error()?;
+
+    let mut message =
+        ton_block::Message::with_ext_in_header(ton_block::ExternalInboundMessageHeader {
+            dst: factory_address().handle_error()?,
+            ..Default::default()
+        });
+    message.set_state_init(parse_embedded_state_init(FACTORY_BOC).handle_error()?);
+    message.set_body(ton_types::SliceData::load_cell(body).handle_error()?);
+
+    ton_types::serialize_toc(&message.serialize().handle_error()?)
+        .map(base64::encode)
+        .handle_error()
+}
+
+pub fn factory_address() -> anyhow::Result<ton_block::MsgAddressInt> {
+    embedded_address(FACTORY_BOC)
+}
+
+pub fn microwave_address() -> anyhow::Result<ton_block::MsgAddressInt> {
+    embedded_address(MICROWAVE_BOC)
+}
+
+fn embedded_address(boc: &str) -> anyhow::Result<ton_block::MsgAddressInt> {
+    let hash = crate::boc::parse_cell(boc)?.repr_hash();
+    ton_block::MsgAddressInt::with_standart(
+        None,
+        0,
+        ton_types::SliceData::from_raw(hash.as_slice().to_vec(), 256),
+    )
+}
+
+fn parse_embedded_state_init(boc: &str) -> anyhow::Result<ton_block::StateInit> {
+    ton_block::StateInit::construct_from_cell(crate::boc::parse_cell(boc)?)
+}
+
+fn make_factory_deploy_body(time: u64, expire_at: u32) -> anyhow::Result<ton_types::Cell> {
+    let contract = ton_abi::Contract::load(FACTORY_ABI.as_bytes())?;
+    let function = contract.function("constructor")?;
+
+    let mut header = std::collections::HashMap::new();
+    header.insert("time".to_string(), ton_abi::TokenValue::Time(time));
+    header.insert("expire".to_string(), ton_abi::TokenValue::Expire(expire_at));
+
+    let inputs = [
+        ton_abi::Token::new(
+            "dest",
+            ton_abi::TokenValue::Address(to_msg_address(microwave_address()?)),
+        ),
+        ton_abi::Token::new(
+            "state_init",
+            ton_abi::TokenValue::Cell(crate::boc::parse_cell(MICROWAVE_BOC)?),
+        ),
+    ];
+    function
+        .encode_input(&header, &inputs, false, None, Some(factory_address()?))?
+        .into_cell()
+}
+
+fn make_microwave_deploy_body(dest: &str, state_init_boc: &str) -> anyhow::Result<ton_types::Cell> {
+    let dest = crate::address::parse_address(dest)?;
+    let state_init = crate::boc::parse_cell(state_init_boc)?;
+    ton_block::StateInit::construct_from_cell(state_init.clone())?;
+
+    let contract = ton_abi::Contract::load(MICROWAVE_ABI.as_bytes())?;
+    let function = contract.function("deploy")?;
+    let inputs = [
+        ton_abi::Token::new("dest", ton_abi::TokenValue::Address(to_msg_address(dest))),
+        ton_abi::Token::new("state_init", ton_abi::TokenValue::Cell(state_init)),
+    ];
+    function.encode_internal_input(&inputs)?.into_cell()
+}
+
+fn to_msg_address(address: ton_block::MsgAddressInt) -> ton_block::MsgAddress {
+    match address {
+        ton_block::MsgAddressInt::AddrStd(address) => ton_block::MsgAddress::AddrStd(address),
+        ton_block::MsgAddressInt::AddrVar(address) => ton_block::MsgAddress::AddrVar(address),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    fn make_state_init(code: u8, data: u8) -> ton_block::StateInit {
+        ton_block::StateInit {
+            code: Some(ton_types::SliceData::new(vec![code, 0x80]).into_cell()),
+            data: Some(ton_types::SliceData::new(vec![data, 0x80]).into_cell()),
+            ..Default::default()
+        }
+    }
+
+    fn make_account(state: ton_block::AccountState) -> ton_block::Account {
+        ton_block::Account::Account(ton_block::AccountStuff {
+            addr: ton_block::MsgAddressInt::from_str(&format!("0:{}", "33".repeat(32))).unwrap(),
+            storage_stat: Default::default(),
+            storage: ton_block::AccountStorage {
+                last_trans_lt: 1,
+                balance: ton_block::CurrencyCollection::with_grams(1_000_000_000),
+                state,
+                init_code_hash: None,
+            },
+        })
+    }
+
+    fn to_boc(cell: &ton_types::Cell) -> String {
+        base64::encode(ton_types::serialize_toc(cell).unwrap())
+    }
+
+    fn account_bocs(account: &ton_block::Account) -> [String; 2] {
+        let shard_account =
+            ton_block::ShardAccount::with_params(account, Default::default(), 1).unwrap();
+        [
+            to_boc(&account.serialize().unwrap()),
+            to_boc(&shard_account.serialize().unwrap()),
+        ]
+    }
+
+    #[test]
+    fn account_formats() {
+        let state_init = make_state_init(0x01, 0x02);
+        let hash = state_init.serialize().unwrap().repr_hash();
+
+        let frozen = make_account(ton_block::AccountState::AccountFrozen {
+            state_init_hash: hash.clone(),
+        });
+        for boc in account_bocs(&frozen) {
+            assert_eq!(frozen_state_hash(&boc).unwrap(), hash);
+        }
+
+        let active = make_account(ton_block::AccountState::AccountActive {
+            state_init: state_init.clone(),
+        });
+        let [account_boc, shard_account_boc] = account_bocs(&active);
+        for boc in [
+            to_boc(&state_init.serialize().unwrap()),
+            account_boc,
+            shard_account_boc,
+        ] {
+            let candidate = parse_candidate_state_init(&boc).unwrap();
+            assert_eq!(candidate.serialize().unwrap().repr_hash(), hash);
+        }
+
+        assert!(frozen_state_hash(&account_bocs(&active)[0]).is_err());
+        assert!(parse_candidate_state_init(&account_bocs(&frozen)[0]).is_err());
+    }
+
+    #[test]
+    fn frozen_state_differences() {
+        let previous = make_state_init(0x01, 0x02);
+        let expected_hash = previous.serialize().unwrap().repr_hash();
+
+        let check = check_frozen_state(&expected_hash, &previous, None).unwrap();
+        assert_eq!(check.actual_hash, expected_hash);
+        assert!(check.differences.is_empty() && check.conclusive);
+
+        // Code and data differences are only known with the pre-freeze state
+        let candidate = make_state_init(0x01, 0x03);
+        let check = check_frozen_state(&expected_hash, &candidate, None).unwrap();
+        assert!(check.differences.is_empty() && !check.conclusive);
+
+        let check = check_frozen_state(&expected_hash, &candidate, Some(&previous)).unwrap();
+        assert_eq!(check.differences, ["data"]);
+        assert!(check.conclusive);
+
+        let candidate = make_state_init(0x04, 0x03);
+        let check = check_frozen_state(&expected_hash, &candidate, Some(&previous)).unwrap();
+        assert_eq!(check.differences, ["code", "data"]);
+
+        // The pre-freeze state is ignored unless it matches the frozen hash
+        let check = check_frozen_state(&expected_hash, &candidate, Some(&candidate)).unwrap();
+        assert!(check.differences.is_empty() && !check.conclusive);
+
+        // Optional fields are found by resetting them
+        let mut candidate = previous.clone();
+        candidate.special = Some(ton_block::TickTock {
+            tick: true,
+            tock: false,
+        });
+        let check = check_frozen_state(&expected_hash, &candidate, None).unwrap();
+        assert_eq!(check.differences, ["special"]);
+        assert!(check.conclusive);
+    }
+
+    fn make_calculator() -> StorageFeeCalculator {
+        // 1 nanotoken per bit per second, cells are free
+        StorageFeeCalculator {
+            prices: vec![ton_block::StoragePrices {
+                utime_since: 0,
+                bit_price_ps: 1 << 16,
+                cell_price_ps: 0,
+                mc_bit_price_ps: 1 << 17,
+                mc_cell_price_ps: 0,
+            }],
+            is_masterchain: false,
+        }
+    }
+
+    fn make_account_stuff(state: ton_block::AccountState, balance: u64) -> ton_block::AccountStuff {
+        let mut account = make_account(state);
+        if let ton_block::Account::Account(stuff) = &mut account {
+            stuff.storage.balance = ton_block::CurrencyCollection::with_grams(balance);
+        }
+        account.update_storage_stat().unwrap();
+        match account {
+            ton_block::Account::Account(mut stuff) => {
+                stuff.storage_stat.last_paid = 1000;
+                stuff
+            }
+            ton_block::Account::AccountNone => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn storage_fees() {
+        let calculator = make_calculator();
+        assert_eq!(calculator.compute_for(1, 100, 1000, 1010), 1000);
+        assert_eq!(calculator.compute_for(1, 100, 1010, 1000), 0);
+        assert_eq!(calculator.compute_for(1, 100, 0, 1000), 0);
+        assert_eq!(calculator.compute_ahead(1, 100, 1000, 10), 1000);
+
+        let calculator = StorageFeeCalculator {
+            is_masterchain: true,
+            ..make_calculator()
+        };
+        assert_eq!(calculator.compute_for(1, 100, 1000, 1010), 2000);
+
+        // Prices change at 2000
+        let mut calculator = make_calculator();
+        calculator.prices.push(ton_block::StoragePrices {
+            utime_since: 2000,
+            bit_price_ps: 3 << 16,
+            cell_price_ps: 0,
+            mc_bit_price_ps: 0,
+            mc_cell_price_ps: 0,
+        });
+        assert_eq!(calculator.compute_for(1, 100, 1500, 2500), 50_000 + 150_000);
+        assert_eq!(calculator.compute_ahead(1, 100, 1500, 10), 1000);
+        assert_eq!(calculator.compute_ahead(1, 100, 2500, 10), 3000);
+    }
+
+    #[test]
+    fn find_times() {
+        assert_eq!(find_time(10, |now| now >= 42), Some(42));
+        assert_eq!(find_time(10, |_| true), Some(10));
+        assert_eq!(find_time(10, |_| false), None);
+        assert_eq!(find_time(0, |now| now == u32::MAX), Some(u32::MAX));
+    }
+
+    #[test]
+    fn storage_deadlines() {
+        let calculator = make_calculator();
+
+        let active = ton_block::AccountState::AccountActive {
+            state_init: make_state_init(0x01, 0x02),
+        };
+        let stuff = make_account_stuff(active.clone(), 1_000_000);
+        let bits = stuff.storage_stat.used.bits() as u32;
+        let (freeze_at, delete_at) =
+            find_storage_deadlines(&stuff, &calculator, 100_000, 1_000_000).unwrap();
+        let freeze_at = freeze_at.unwrap();
+        assert_eq!(freeze_at, 1000 + 1_100_000 / bits + 1);
+        let delete_at = delete_at.unwrap();
+        assert!(delete_at > freeze_at);
+
+        // A zero balance account is frozen right after the last payment
+        let stuff = make_account_stuff(active, 0);
+        let (freeze_at, _) = find_storage_deadlines(&stuff, &calculator, 0, 1_000_000).unwrap();
+        assert_eq!(freeze_at, Some(1001));
+
+        // An already frozen account is only deleted
+        let frozen = ton_block::AccountState::AccountFrozen {
+            state_init_hash: Default::default(),
+        };
+        let stuff = make_account_stuff(frozen, 1_000_000);
+        let bits = stuff.storage_stat.used.bits() as u32;
+        let (freeze_at, delete_at) =
+            find_storage_deadlines(&stuff, &calculator, 100_000, 1_000_000).unwrap();
+        assert_eq!(freeze_at, None);
+        assert_eq!(delete_at, Some(1000 + 2_000_000 / bits + 1));
+    }
+}
@@ -0,0 +1,506 @@
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+use ton_abi::ParamType;
+use ton_types::{Cell, SliceData};
+use wasm_bindgen::prelude::*;
+
+use shared::*;
+
+use crate::slice::{read_value, SliceFormat};
+
+/// Annotates the first BOC root with the fields of the layout.
+///
+/// The layout is either a cell structure or a function signature in the `abi-parser` syntax.
+/// A function is treated as an internal message body, prefixed with its input id.
+///
+/// Fields are read following the ABI 2.x chain rules: when the data of a cell is exhausted,
+/// the decoding continues in its only remaining reference (unless it belongs to the last
+/// field), and large optionals are stored in a separate reference. Decoding stops at the
+/// first field which doesn't match the data; the error is reported along with its position.
+#[wasm_bindgen(js_name = "overlayLayout")]
+pub fn overlay_layout(boc: &str, layout: &str) -> Result<String, JsValue> {
+    let cell = crate::boc::parse_cell(boc).handle_error()?;
+    overlay(cell, layout)
+        .map(|overlay| overlay.to_string())
+        .handle_error()
+}
+
+/// Annotates the cell with the fields of the layout, see [`overlay_layout`].
+pub fn overlay(cell: Cell, layout: &str) -> Result<Value> {
+    let (function_id, params) = match abi_parser::Entity::parse(layout)? {
+        abi_parser::Entity::Empty => (None, Vec::new()),
+        abi_parser::Entity::Cell(params) => (None, params),
+        abi_parser::Entity::Function(function) => (Some(function.input_id), function.inputs),
+    };
+
+    let mut overlay = Overlay::default();
+    let mut cursor = Cursor::new(cell, String::new())?;
+
+    let result = (|| {
+        if let Some(id) = function_id {
+            overlay.read_param(
+                &mut cursor,
+                "function_id",
+                &ParamType::Uint(32),
+                params.is_empty(),
+            )?;
+            let actual = overlay
+                .ranges
+                .last()
+                .and_then(|range| range["value"].as_str());
+            if actual != Some(id.to_string().as_str()) {
+                return Err(anyhow!("Function id mismatch: expected {id}"));
+            }
+        }
+        for (i, param) in params.iter().enumerate() {
+            let last = i + 1 == params.len();
+            overlay.read_param(&mut cursor, &param.name, &param.kind, last)?;
+        }
+        Ok(())
+    })();
+
+    let error = match result {
+        Ok(()) => Value::Null,
+        Err(e) => serde_json::json!({
+            "path": overlay.current_path,
+            "cell": cursor.path,
+            "offset": cursor.offset(),
+            "message": e.to_string(),
+        }),
+    };
+
+    Ok(serde_json::json!({
+        "ranges": overlay.ranges,
+        "complete": error.is_null()
+            && cursor.slice.remaining_bits() == 0
+            && cursor.slice.remaining_references() == 0,
+        "error": error,
+        "trailing": {
+            "cell": cursor.path,
+            "offset": cursor.offset(),
+            "bits": cursor.slice.remaining_bits(),
+            "refs": cursor.slice.remaining_references(),
+        },
+    }))
+}
+
+#[derive(Default)]
+struct Overlay {
+    ranges: Vec<Value>,
+    current_path: String,
+}
+
+impl Overlay {
+    fn read_param(
+        &mut self,
+        cursor: &mut Cursor,
+        path: &str,
+        kind: &ParamType,
+        last: bool,
+    ) -> Result<()> {
+        self.current_path = path.to_string();
+        match kind {
+            ParamType::Uint(bits) => self.read_bits(cursor, path, kind, *bits, SliceFormat::Uint),
+            ParamType::Int(bits) => self.read_bits(cursor, path, kind, *bits, SliceFormat::Int),
+            ParamType::Bool => self.read_bits(cursor, path, kind, 1, SliceFormat::Uint),
+            ParamType::Time => self.read_bits(cursor, path, kind, 64, SliceFormat::Uint),
+            ParamType::Expire => self.read_bits(cursor, path, kind, 32, SliceFormat::Uint),
+            ParamType::FixedBytes(len) => {
+                self.read_bits(cursor, path, kind, len * 8, SliceFormat::Hex)
+            }
+            ParamType::Token => self.read_var(cursor, path, kind, 16, false),
+            ParamType::VarUint(size) => self.read_var(cursor, path, kind, *size, false),
+            ParamType::VarInt(size) => self.read_var(cursor, path, kind, *size, true),
+            ParamType::Address | ParamType::AddressStd => {
+                cursor.ensure_bits(2)?;
+                let offset = cursor.offset();
+                let value = read_value(&mut cursor.slice, None, SliceFormat::Address)?;
+                self.push(cursor, path, kind, offset, cursor.offset() - offset, value);
+                Ok(())
+            }
+            ParamType::PublicKey => {
+                let flag = self.read_flag(cursor, &format!("{path}.flag"))?;
+                if flag {
+                    self.read_bits(cursor, path, kind, 256, SliceFormat::Hex)?;
+                }
+                Ok(())
+            }
+            ParamType::Cell | ParamType::Bytes | ParamType::String => {
+                self.read_ref(cursor, path, kind, last).map(|_| ())
+            }
+            ParamType::Ref(inner) => {
+                let mut child = self.read_ref(cursor, path, kind, last)?;
+                self.read_param(&mut child, path, inner, true)
+            }
+            ParamType::Optional(inner) => {
+                if self.read_flag(cursor, &format!("{path}.flag"))? {
+                    if is_large_optional(inner) {
+                        let mut child = self.read_ref(cursor, path, kind, last)?;
+                        self.read_param(&mut child, path, inner, true)?;
+                    } else {
+                        self.read_param(cursor, path, inner, last)?;
+                    }
+                }
+                Ok(())
+            }
+            ParamType::Array(_) => {
+                self.read_bits(
+                    cursor,
+                    &format!("{path}.length"),
+                    &ParamType::Uint(32),
+                    32,
+                    SliceFormat::Uint,
+                )?;
+                self.read_dict(cursor, path, kind, last)
+            }
+            ParamType::FixedArray(..) | ParamType::Map(..) => {
+                self.read_dict(cursor, path, kind, last)
+            }
+            ParamType::Tuple(params) => {
+                for (i, param) in params.iter().enumerate() {
+                    let last = last && i + 1 == params.len();
+                    let path = format!("{path}.{}", param.name);
+                    self.read_param(cursor, &path, &param.kind, last)?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn read_bits(
+        &mut self,
+        cursor: &mut Cursor,
+        path: &str,
+        kind: &ParamType,
+        bits: usize,
+        format: SliceFormat,
+    ) -> Result<()> {
+        cursor.ensure_bits(bits)?;
+        let offset = cursor.offset();
+        let mut value = read_value(&mut cursor.slice, Some(bits), format)?;
+        if matches!(kind, ParamType::Bool) {
+            value = (value == "1").to_string();
+        }
+        self.push(cursor, path, kind, offset, bits, value);
+        Ok(())
+    }
+
+    fn read_var(
+        &mut self,
+        cursor: &mut Cursor,
+        path: &str,
+        kind: &ParamType,
+        size: usize,
+        signed: bool,
+    ) -> Result<()> {
+        let len_bits = 32 - (size as u32 - 1).leading_zeros() as usize;
+        cursor.ensure_bits(len_bits)?;
+        let offset = cursor.offset();
+        let len = cursor.slice.get_next_int(len_bits)? as usize;
+        if len >= size {
+            return Err(anyhow!("Invalid length {len} of varint{size}"));
+        }
+        let format = if signed && len > 0 {
+            SliceFormat::Int
+        } else {
+            SliceFormat::Uint
+        };
+        let value = read_value(&mut cursor.slice, Some(len * 8), format)?;
+        self.push(cursor, path, kind, offset, len_bits + len * 8, value);
+        Ok(())
+    }
+
+    fn read_flag(&mut self, cursor: &mut Cursor, path: &str) -> Result<bool> {
+        self.read_bits(cursor, path, &ParamType::Bool, 1, SliceFormat::Uint)?;
+        Ok(self
+            .ranges
+            .last()
+            .map(|range| range["value"] == "true")
+            .unwrap_or_default())
+    }
+
+    fn read_dict(
+        &mut self,
+        cursor: &mut Cursor,
+        path: &str,
+        kind: &ParamType,
+        last: bool,
+    ) -> Result<()> {
+        if self.read_flag(cursor, &format!("{path}.flag"))? {
+            self.read_ref(cursor, path, kind, last)?;
+        }
+        Ok(())
+    }
+
+    fn read_ref(
+        &mut self,
+        cursor: &mut Cursor,
+        path: &str,
+        kind: &ParamType,
+        last: bool,
+    ) -> Result<Cursor> {
+        let (index, cell) = cursor.next_ref(last)?;
+        self.ranges.push(serde_json::json!({
+            "path": path,
+            "type": kind.type_signature(),
+            "cell": cursor.path,
+            "ref": index,
+            "value": cell.repr_hash().to_hex_string(),
+        }));
+        Cursor::new(cell, join_path(&cursor.path, index))
+    }
+
+    fn push(
+        &mut self,
+        cursor: &Cursor,
+        path: &str,
+        kind: &ParamType,
+        offset: usize,
+        bits: usize,
+        value: String,
+    ) {
+        self.ranges.push(serde_json::json!({
+            "path": path,
+            "type": kind.type_signature(),
+            "cell": cursor.path,
+            "offset": offset,
+            "bits": bits,
+            "value": value,
+        }));
+    }
+}
+
+struct Cursor {
+    cell: Cell,
+    slice: SliceData,
+    /// Path of the current cell in the `0.1.0` notation.
+    path: String,
+}
+
+impl Cursor {
+    fn new(cell: Cell, path: String) -> Result<Self> {
+        Ok(Self {
+            slice: SliceData::load_cell(cell.clone())?,
+            cell,
+            path,
+        })
+    }
+
+    fn offset(&self) -> usize {
+        self.cell.bit_length() - self.slice.remaining_bits()
+    }
+
+    fn ensure_bits(&mut self, bits: usize) -> Result<()> {
+        if self.slice.remaining_bits() == 0 && bits > 0 && self.slice.remaining_references() > 0 {
+            self.move_to_next_cell()?;
+        }
+        if self.slice.remaining_bits() < bits {
+            return Err(anyhow!(
+                "Not enough data: expected {bits} bits, {} left",
+                self.slice.remaining_bits()
+            ));
+        }
+        Ok(())
+    }
+
+    /// Reads the next reference. The only remaining reference of a cell without data
+    /// is a continuation, unless the reference belongs to the last field.
+    fn next_ref(&mut self, last: bool) -> Result<(usize, Cell)> {
+        if !last && self.slice.remaining_references() == 1 && self.slice.remaining_bits() == 0 {
+            self.move_to_next_cell()?;
+        }
+        let index = self.cell.references_count() - self.slice.remaining_references();
+        let cell = self
+            .slice
+            .checked_drain_reference()
+            .map_err(|_| anyhow!("Not enough references"))?;
+        Ok((index, cell))
+    }
+
+    fn move_to_next_cell(&mut self) -> Result<()> {
+        if self.slice.remaining_references() != 1 {
+            return Err(anyhow!("Ambiguous continuation cell"));
+        }
+        let index = self.cell.references_count() - 1;
+        let cell = self.slice.reference(0)?;
+        *self = Cursor::new(cell, join_path(&self.path, index))?;
+        Ok(())
+    }
+}
+
+/// Optionals which can't fit into a cell are stored in a separate reference.
+fn is_large_optional(kind: &ParamType) -> bool {
+    max_bit_size(kind) >= MAX_CELL_BITS || max_refs_count(kind) >= MAX_CELL_REFS
+}
+
+const MAX_CELL_BITS: usize = 1023;
+const MAX_CELL_REFS: usize = 4;
+
+/// The maximum number of bits of the inline part of the value, as in `ton_abi`.
+fn max_bit_size(kind: &ParamType) -> usize {
+    match kind {
+        ParamType::Uint(bits) | ParamType::Int(bits) => *bits,
+        ParamType::VarUint(size) | ParamType::VarInt(size) => {
+            let len_bits = 32 - (*size as u32 - 1).leading_zeros() as usize;
+            len_bits + (size - 1) * 8
+        }
+        ParamType::Bool => 1,
+        ParamType::Array(_) => 33,
+        ParamType::FixedArray(..) | ParamType::Map(..) => 1,
+        ParamType::Cell | ParamType::Bytes | ParamType::String | ParamType::Ref(_) => 0,
+        ParamType::Address => 591,
+        ParamType::AddressStd => 267,
+        ParamType::FixedBytes(len) => len * 8,
+        ParamType::Token => 124,
+        ParamType::Time => 64,
+        ParamType::Expire => 32,
+        ParamType::PublicKey => 257,
+        ParamType::Optional(inner) => {
+            if is_large_optional(inner) {
+                1
+            } else {
+                1 + max_bit_size(inner)
+            }
+        }
+        ParamType::Tuple(params) => params.iter().map(|param| max_bit_size(&param.kind)).sum(),
+    }
+}
+
+/// The maximum number of references of the inline part of the value, as in `ton_abi`.
+fn max_refs_count(kind: &ParamType) -> usize {
+    match kind {
+        ParamType::Array(_)
+        | ParamType::FixedArray(..)
+        | ParamType::Map(..)
+        | ParamType::Cell
+        | ParamType::Bytes
+        | ParamType::String
+        | ParamType::Ref(_) => 1,
+        ParamType::Optional(inner) => {
+            if is_large_optional(inner) {
+                1
+            } else {
+                max_refs_count(inner)
+            }
+        }
+        ParamType::Tuple(params) => params.iter().map(|param| max_refs_count(&param.kind)).sum(),
+        _ => 0,
+    }
+}
+
+fn join_path(path: &str, index: usize) -> String {
+    if path.is_empty() {
+        index.to_string()
+    } else {
+        format!("{path}.{index}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn overlay_cell(cell: &str, layout: &str) -> Value {
+        overlay(abi_parser::cell::build_cell(cell).unwrap(), layout).unwrap()
+    }
+
+    fn range(path: &str, cell: &str, offset: usize, bits: usize, value: &str) -> Value {
+        serde_json::json!({
+            "path": path,
+            "cell": cell,
+            "offset": offset,
+            "bits": bits,
+            "value": value,
+        })
+    }
+
+    /// Ranges without types and reference hashes.
+    fn ranges(overlay: &Value) -> Vec<Value> {
+        overlay["ranges"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|range| {
+                let mut range = range.clone();
+                let range = range.as_object_mut().unwrap();
+                range.remove("type");
+                if range.contains_key("ref") {
+                    range.remove("value");
+                }
+                Value::Object(range.clone())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn references() {
+        let result = overlay_cell("u32(5) { u8(7) }", "uint32, ref(uint8)");
+        assert_eq!(result["complete"], true);
+        assert_eq!(
+            ranges(&result),
+            [
+                range("value0", "", 0, 32, "5"),
+                serde_json::json!({ "path": "value1", "cell": "", "ref": 0 }),
+                range("value1", "0", 0, 8, "7"),
+            ]
+        );
+    }
+
+    #[test]
+    fn chained_cells() {
+        let result = overlay_cell(
+            "u256(1) u256(2) u256(3) { u256(4) u32(5) }",
+            "uint256, uint256, uint256, uint256, uint32",
+        );
+        assert_eq!(result["complete"], true);
+        let ranges = ranges(&result);
+        assert_eq!(ranges[2], range("value2", "", 512, 256, "3"));
+        assert_eq!(ranges[3], range("value3", "0", 0, 256, "4"));
+        assert_eq!(ranges[4], range("value4", "0", 256, 32, "5"));
+
+        // The only reference of the last field is not a continuation
+        let result = overlay_cell("u32(1) { }", "uint32, cell");
+        assert_eq!(result["complete"], true);
+        assert_eq!(ranges(&result)[1]["cell"], "");
+    }
+
+    #[test]
+    fn optionals() {
+        let result = overlay_cell("b{1} u8(5) u8(6)", "optional(uint8), uint8");
+        assert_eq!(
+            ranges(&result),
+            [
+                range("value0.flag", "", 0, 1, "true"),
+                range("value0", "", 1, 8, "5"),
+                range("value1", "", 9, 8, "6"),
+            ]
+        );
+
+        let result = overlay_cell("b{0} u8(6)", "optional(uint8), uint8");
+        assert_eq!(result["complete"], true);
+        assert_eq!(ranges(&result)[1], range("value1", "", 1, 8, "6"));
+
+        // Optionals which don't fit into a cell are stored in a reference
+        let result = overlay_cell(
+            "b{1} { u256(1) u256(2) u256(3) u255(4) }",
+            "optional((uint256, uint256, uint256, uint255))",
+        );
+        assert_eq!(result["complete"], true);
+        let ranges = ranges(&result);
+        assert_eq!(
+            ranges[1],
+            serde_json::json!({ "path": "value0", "cell": "", "ref": 0 })
+        );
+        assert_eq!(ranges[2], range("value0.value0", "0", 0, 256, "1"));
+        assert_eq!(ranges[5], range("value0.value3", "0", 768, 255, "4"));
+    }
+
+    #[test]
+    fn mismatch() {
+        let result = overlay_cell("u32(1) u8(2)", "uint32, uint32");
+        assert_eq!(result["complete"], false);
+        assert_eq!(result["error"]["path"], "value1");
+        assert_eq!(result["error"]["offset"], 32);
+        assert_eq!(result["trailing"]["bits"], 8);
+    }
+}
@@ -87,7 +87,7 @@ pub fn parse(abi: &str) -> Result<AbiEntity, JsValue> {
     .unchecked_into())
 }
 
-fn make_params(params: Vec<Param>) -> JsValue {
+pub(crate) fn make_params(params: Vec<Param>) -> JsValue {
     let result = js_sys::Array::new();
     for param in params {
         result.push(&make_param(param));
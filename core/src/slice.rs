@@ -0,0 +1,317 @@
+use std::fmt::Write;
+
+use anyhow::{anyhow, Result};
+use num_bigint::{BigInt, BigUint};
+use ton_block::{Deserializable, MsgAddress};
+use ton_types::{Cell, SliceData};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+use shared::*;
+
+#[wasm_bindgen(typescript_custom_section)]
+const SLICE: &str = r#"
+export type SliceFormat = 'hex' | 'binary' | 'uint' | 'int' | 'address' | 'varuint';
+
+export type SliceReadOptions = {
+    offset?: number,
+    bits?: number,
+    format?: SliceFormat,
+    varUintSize?: number,
+};
+
+export type SliceValue = {
+    value: string,
+    offset: number,
+    bits: number,
+    remainingBits: number,
+    remainingRefs: number,
+};
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "SliceReadOptions | undefined")]
+    pub type OptionalSliceReadOptions;
+
+    #[wasm_bindgen(typescript_type = "SliceValue")]
+    pub type SliceValue;
+}
+
+/// Reads a bit range of the cell at the specified path (e.g. `0.1.0`) of the first BOC root.
+///
+/// `bits` is ignored for addresses and `varuint`s which have their own length.
+#[wasm_bindgen(js_name = "readCellSlice")]
+pub fn read_cell_slice(
+    boc: &str,
+    path: &str,
+    options: OptionalSliceReadOptions,
+) -> Result<SliceValue, JsValue> {
+    let options = SliceReadOptions::from_js(&options).handle_error()?;
+    let cell = navigate(crate::boc::parse_cell(boc).handle_error()?, path).handle_error()?;
+
+    let mut slice = SliceData::load_cell(cell).handle_error()?;
+    slice.move_by(options.offset).handle_error()?;
+    let start = slice.remaining_bits();
+    let value = read_value(&mut slice, options.bits, options.format).handle_error()?;
+
+    Ok(ObjectBuilder::new()
+        .set("value", value)
+        .set("offset", options.offset as u32)
+        .set("bits", (start - slice.remaining_bits()) as u32)
+        .set("remainingBits", slice.remaining_bits() as u32)
+        .set("remainingRefs", slice.remaining_references() as u32)
+        .build()
+        .unchecked_into())
+}
+
+/// Prints the cell at the specified path in the Fift `x{...}` notation.
+///
+/// When `bits` is specified, only the data of that range is printed without references.
+#[wasm_bindgen(js_name = "formatFiftSlice")]
+pub fn format_fift_slice(
+    boc: &str,
+    path: &str,
+    options: OptionalSliceReadOptions,
+) -> Result<String, JsValue> {
+    let options = SliceReadOptions::from_js(&options).handle_error()?;
+    let cell = navigate(crate::boc::parse_cell(boc).handle_error()?, path).handle_error()?;
+
+    let mut slice = SliceData::load_cell(cell).handle_error()?;
+    slice.move_by(options.offset).handle_error()?;
+    if let Some(bits) = options.bits {
+        slice = slice.get_next_slice(bits).handle_error()?;
+    }
+
+    let mut result = String::new();
+    write_fift(&mut result, &slice, 0).handle_error()?;
+    Ok(result)
+}
+
+/// Follows the dot-separated reference indices starting from the root.
+pub fn navigate(root: Cell, path: &str) -> Result<Cell> {
+    let path = path.trim();
+    if path.is_empty() {
+        return Ok(root);
+    }
+
+    path.split('.').try_fold(root, |cell, index| {
+        let index = index
+            .trim()
+            .parse::<usize>()
+            .map_err(|_| anyhow!("Invalid path segment: {index}"))?;
+        cell.reference(index)
+            .map_err(|_| anyhow!("Cell has no reference {index}"))
+    })
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum SliceFormat {
+    Hex,
+    Binary,
+    Uint,
+    Int,
+    Address,
+    VarUint(usize),
+}
+
+struct SliceReadOptions {
+    offset: usize,
+    bits: Option<usize>,
+    format: SliceFormat,
+}
+
+impl SliceReadOptions {
+    fn from_js(options: &JsValue) -> Result<Self> {
+        let mut result = Self {
+            offset: 0,
+            bits: None,
+            format: SliceFormat::Hex,
+        };
+        if options.is_undefined() || options.is_null() {
+            return Ok(result);
+        }
+
+        let get = |key: &str| {
+            js_sys::Reflect::get(options, &JsValue::from_str(key)).unwrap_or(JsValue::UNDEFINED)
+        };
+
+        if let Some(offset) = get("offset").as_f64() {
+            result.offset = offset as usize;
+        }
+        if let Some(bits) = get("bits").as_f64() {
+            result.bits = Some(bits as usize);
+        }
+        if let Some(format) = get("format").as_string() {
+            result.format = match format.as_str() {
+                "hex" => SliceFormat::Hex,
+                "binary" => SliceFormat::Binary,
+                "uint" => SliceFormat::Uint,
+                "int" => SliceFormat::Int,
+                "address" => SliceFormat::Address,
+                "varuint" => {
+                    let size = get("varUintSize").as_f64().unwrap_or(16.0) as usize;
+                    if !(2..=32).contains(&size) {
+                        return Err(anyhow!("Invalid VarUInteger size: {size}"));
+                    }
+                    SliceFormat::VarUint(size)
+                }
+                _ => return Err(anyhow!("Unknown slice format")),
+            };
+        }
+
+        Ok(result)
+    }
+}
+
+pub fn read_value(
+    slice: &mut SliceData,
+    bits: Option<usize>,
+    format: SliceFormat,
+) -> Result<String> {
+    let take = |slice: &mut SliceData| {
+        let bits = bits.unwrap_or_else(|| slice.remaining_bits());
+        slice.get_next_slice(bits)
+    };
+
+    Ok(match format {
+        SliceFormat::Hex => take(slice)?.to_hex_string(),
+        SliceFormat::Binary => {
+            let data = take(slice)?;
+            let bytes = data.get_bytestring(0);
+            (0..data.remaining_bits())
+                .map(|i| {
+                    if bytes[i / 8] & (0x80 >> (i % 8)) != 0 {
+                        '1'
+                    } else {
+                        '0'
+                    }
+                })
+                .collect()
+        }
+        SliceFormat::Uint => to_biguint(&take(slice)?).to_string(),
+        SliceFormat::Int => {
+            let data = take(slice)?;
+            let len = data.remaining_bits();
+            let value = BigInt::from(to_biguint(&data));
+            if len > 0 && data.get_bits(0, 1)? == 1 {
+                (value - (BigInt::from(1) << len)).to_string()
+            } else {
+                value.to_string()
+            }
+        }
+        SliceFormat::Address => MsgAddress::construct_from(slice)?.to_string(),
+        SliceFormat::VarUint(size) => {
+            let len_bits = 32 - (size as u32 - 1).leading_zeros() as usize;
+            let len = slice.get_next_int(len_bits)? as usize;
+            if len >= size {
+                return Err(anyhow!("VarUInteger length {len} exceeds its size {size}"));
+            }
+            to_biguint(&slice.get_next_slice(len * 8)?).to_string()
+        }
+    })
+}
+
+fn to_biguint(data: &SliceData) -> BigUint {
+    let bytes = data.get_bytestring(0);
+    BigUint::from_bytes_be(&bytes) >> (bytes.len() * 8 - data.remaining_bits())
+}
+
+fn write_fift(result: &mut String, slice: &SliceData, indent: usize) -> Result<()> {
+    let _ = writeln!(
+        result,
+        "{:indent$}x{{{}}}",
+        "",
+        slice.to_hex_string(),
+        indent = indent
+    );
+    for i in 0..slice.remaining_references() {
+        let child = SliceData::load_cell(slice.reference(i)?)?;
+        write_fift(result, &child, indent + 1)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use ton_block::Serializable;
+
+    use super::*;
+
+    fn make_tree() -> Cell {
+        abi_parser::cell::build_cell("u8(0xab) { u16(0x1234) } { u8(0xcd) { u8(0xef) } }").unwrap()
+    }
+
+    fn read(cell: Cell, bits: Option<usize>, format: SliceFormat) -> Result<String> {
+        read_value(&mut SliceData::load_cell(cell)?, bits, format)
+    }
+
+    #[test]
+    fn navigation() {
+        let root = make_tree();
+        assert_eq!(navigate(root.clone(), "").unwrap(), root);
+        assert_eq!(
+            navigate(root.clone(), " 0 ").unwrap(),
+            root.reference(0).unwrap()
+        );
+
+        let cell = navigate(root.clone(), "1.0").unwrap();
+        assert_eq!(cell.data()[0], 0xef);
+
+        assert!(navigate(root.clone(), "2").is_err());
+        assert!(navigate(root.clone(), "0.0").is_err());
+        assert!(navigate(root.clone(), "1.").is_err());
+        assert!(navigate(root, "a").is_err());
+    }
+
+    #[test]
+    fn values() {
+        let root = make_tree();
+        let child = navigate(root.clone(), "0").unwrap();
+
+        assert_eq!(
+            read(child.clone(), None, SliceFormat::Uint).unwrap(),
+            "4660"
+        );
+        assert_eq!(
+            read(child.clone(), Some(8), SliceFormat::Hex).unwrap(),
+            "12"
+        );
+        assert_eq!(
+            read(root.clone(), Some(4), SliceFormat::Binary).unwrap(),
+            "1010"
+        );
+        assert_eq!(read(root.clone(), None, SliceFormat::Int).unwrap(), "-85");
+        assert_eq!(
+            read(child.clone(), Some(8), SliceFormat::Int).unwrap(),
+            "18"
+        );
+
+        // Out of range reads
+        assert!(read(child, Some(17), SliceFormat::Uint).is_err());
+        let mut slice = SliceData::load_cell(root).unwrap();
+        slice.move_by(8).unwrap();
+        assert!(read_value(&mut slice, Some(1), SliceFormat::Hex).is_err());
+
+        let address = format!("0:{}", "33".repeat(32));
+        let cell = ton_block::MsgAddressInt::from_str(&address)
+            .unwrap()
+            .serialize()
+            .unwrap();
+        assert_eq!(read(cell, None, SliceFormat::Address).unwrap(), address);
+
+        let cell = abi_parser::cell::build_cell("u4(2) u16(1000)").unwrap();
+        assert_eq!(read(cell, None, SliceFormat::VarUint(16)).unwrap(), "1000");
+        let cell = abi_parser::cell::build_cell("u3(6) u8(0)").unwrap();
+        assert!(read(cell, None, SliceFormat::VarUint(5)).is_err());
+    }
+
+    #[test]
+    fn fift_slice() {
+        let mut result = String::new();
+        write_fift(&mut result, &SliceData::load_cell(make_tree()).unwrap(), 0).unwrap();
+        assert_eq!(result.to_lowercase(), "x{ab}\n x{1234}\n x{cd}\n  x{ef}\n");
+    }
+}
@@ -0,0 +1,566 @@
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+
+use anyhow::{anyhow, Result};
+use serde_json::{json, Map, Value};
+use shared::*;
+use ton_abi::TokenValue;
+use ton_block::Serializable;
+use ton_types::{BuilderData, Cell, HashmapE, HashmapType, SliceData, UInt256};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+/// Decodes the persistent data of the contract using ABI `fields`.
+///
+/// Accepts an account or a StateInit BOC. Data of a contract which is not deployed yet
+/// (the init data dictionary of ABI before 2.4) is decoded using the ABI `data` section.
+#[wasm_bindgen(js_name = "decodeAccountData")]
+pub fn decode_account_data(boc: &str, abi: &str) -> Result<String, JsValue> {
+    let state_init = crate::microwave::parse_candidate_state_init(boc).handle_error()?;
+    let data = state_init.data.ok_or("State has no data").handle_error()?;
+    decode_data(abi, data)
+        .map(|value| value.to_string())
+        .handle_error()
+}
+
+fn decode_data(abi: &str, data: Cell) -> Result<Value> {
+    let contract = ton_abi::Contract::load(abi.as_bytes())?;
+    let raw = serde_json::from_str::<Value>(abi)?;
+
+    // ABI 2.4 stores init data in the fields layout, older versions use the `data`
+    // dictionary until the contract is deployed
+    let is_init_data = if has_init_fields(&raw) || contract.data.is_empty() {
+        false
+    } else if contract.fields.is_empty() {
+        true
+    } else {
+        // Init data is stored as a `HashmapE 64`, so its root has only the presence bit
+        data.bit_length() == 1 && data.references_count() <= 1
+    };
+
+    if is_init_data {
+        decode_init_data(&contract, data)
+    } else {
+        decode_fields(&contract, data)
+    }
+}
+
+/// Whether the ABI marks init fields (ABI 2.4+).
+fn has_init_fields(raw: &Value) -> bool {
+    raw["fields"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .any(|field| field.get("init").is_some())
+}
+
+fn decode_fields(contract: &ton_abi::Contract, data: Cell) -> Result<Value> {
+    if contract.fields.is_empty() {
+        return Err(anyhow!("ABI has no fields"));
+    }
+
+    let tokens = TokenValue::decode_params(
+        &contract.fields,
+        SliceData::load_cell(data)?,
+        &contract.abi_version,
+        false,
+    )?;
+
+    let mut pubkey = None;
+    let mut timestamp = None;
+    let mut constructor_flag = None;
+    let mut fields = Vec::with_capacity(tokens.len());
+    for token in tokens {
+        match (token.name.as_str(), &token.value) {
+            ("_pubkey", TokenValue::Uint(value)) => pubkey = Some(format!("{:064x}", value.number)),
+            ("_timestamp", TokenValue::Uint(value)) => timestamp = Some(value.number.to_string()),
+            ("_constructorFlag", TokenValue::Bool(value)) => constructor_flag = Some(*value),
+            _ => fields.push(token),
+        }
+    }
+
+    Ok(json!({
+        "layout": "fields",
+        "initialized": constructor_flag.unwrap_or(true),
+        "pubkey": pubkey,
+        "timestamp": timestamp,
+        "constructorFlag": constructor_flag,
+        "fields": ton_abi::token::Detokenizer::detokenize_to_json_value(&fields)?,
+    }))
+}
+
+fn decode_init_data(contract: &ton_abi::Contract, data: Cell) -> Result<Value> {
+    let map = HashmapE::with_hashmap(64, data.reference(0).ok());
+
+    let pubkey = match map.get(make_key(0)?)? {
+        Some(mut value) if value.remaining_bits() >= 256 => {
+            Some(hex::encode(value.get_next_bytes(32)?))
+        }
+        _ => None,
+    };
+
+    let items = contract
+        .data
+        .values()
+        .map(|item| (item.key, &item.value))
+        .collect::<BTreeMap<_, _>>();
+
+    let mut fields = Map::new();
+    for (key, param) in items {
+        let value = match map.get(make_key(key)?)? {
+            Some(value) => {
+                let tokens = TokenValue::decode_params(
+                    std::slice::from_ref(param),
+                    value,
+                    &contract.abi_version,
+                    false,
+                )?;
+                ton_abi::token::Detokenizer::detokenize_to_json_value(&tokens)?
+                    .get(&param.name)
+                    .cloned()
+                    .unwrap_or_default()
+            }
+            None => Value::Null,
+        };
+        fields.insert(param.name.clone(), value);
+    }
+
+    Ok(json!({
+        "layout": "initData",
+        "initialized": false,
+        "pubkey": pubkey,
+        "timestamp": Value::Null,
+        "constructorFlag": Value::Null,
+        "fields": fields,
+    }))
+}
+
+fn make_key(key: u64) -> Result<SliceData> {
+    let mut builder = BuilderData::new();
+    builder.append_u64(key)?;
+    SliceData::load_builder(builder)
+}
+
+#[wasm_bindgen(typescript_custom_section)]
+const STATE_INIT_PARAMS: &str = r#"
+export type StateInitParams = {
+    code: string,
+    abi?: string,
+    initParams?: string | { [name: string]: any },
+    publicKey?: string,
+    data?: string,
+    libraries?: { boc: string, public?: boolean }[],
+    splitDepth?: number,
+    special?: { tick: boolean, tock: boolean },
+    workchain?: number,
+};
+
+export type BuiltStateInit = {
+    boc: string,
+    hash: string,
+    address: string,
+};
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "StateInitParams")]
+    pub type StateInitParamsJs;
+
+    #[wasm_bindgen(typescript_type = "BuiltStateInit")]
+    pub type BuiltStateInit;
+}
+
+/// Builds a StateInit and computes the address of the contract.
+///
+/// Initial data is either `data` as is, or built from the ABI: init `fields` for ABI 2.4+
+/// and the `data` section dictionary for older versions. All init params are required.
+/// The public key is stored as `_pubkey` or under the key 0 respectively, and is zero
+/// when `publicKey` is not specified (as for contracts deployed by other contracts).
+#[wasm_bindgen(js_name = "buildStateInit")]
+pub fn build_state_init(params: StateInitParamsJs) -> Result<BuiltStateInit, JsValue> {
+    let params = StateInitParams::from_js(&params).handle_error()?;
+
+    let data = match (&params.data, &params.abi) {
+        (Some(data), _) => crate::boc::parse_cell(data).handle_error()?,
+        (None, Some(abi)) => {
+            make_initial_data(abi, &params.init_params, params.public_key).handle_error()?
+        }
+        (None, None) => Cell::default(),
+    };
+
+    let mut state_init = ton_block::StateInit {
+        code: Some(crate::boc::parse_cell(&params.code).handle_error()?),
+        data: Some(data),
+        ..Default::default()
+    };
+    if let Some(depth) = params.split_depth {
+        state_init.split_depth = Some(ton_block::Number5::new(depth).handle_error()?);
+    }
+    if let Some((tick, tock)) = params.special {
+        state_init.special = Some(ton_block::TickTock { tick, tock });
+    }
+    for (library, public) in &params.libraries {
+        let root = crate::boc::parse_cell(library).handle_error()?;
+        state_init
+            .library
+            .set(&root.repr_hash(), &ton_block::SimpleLib::new(root, *public))
+            .handle_error()?;
+    }
+
+    let cell = state_init.serialize().handle_error()?;
+    let boc = ton_types::serialize_toc(&cell).handle_error()?;
+    let hash = cell.repr_hash().to_hex_string();
+
+    Ok(ObjectBuilder::new()
+        .set("boc", base64::encode(boc))
+        .set("address", format!("{}:{}", params.workchain, hash))
+        .set("hash", hash)
+        .build()
+        .unchecked_into())
+}
+
+struct StateInitParams {
+    code: String,
+    abi: Option<String>,
+    init_params: Value,
+    public_key: Option<UInt256>,
+    data: Option<String>,
+    libraries: Vec<(String, bool)>,
+    split_depth: Option<u32>,
+    special: Option<(bool, bool)>,
+    workchain: i8,
+}
+
+impl StateInitParams {
+    fn from_js(params: &JsValue) -> Result<Self> {
+        let get = |value: &JsValue, key: &str| {
+            js_sys::Reflect::get(value, &JsValue::from_str(key)).unwrap_or(JsValue::UNDEFINED)
+        };
+
+        let code = get(params, "code")
+            .as_string()
+            .ok_or_else(|| anyhow!("Code is required"))?;
+
+        let init_params = get(params, "initParams");
+        let init_params = if let Some(init_params) = init_params.as_string() {
+            serde_json::from_str(&init_params)?
+        } else if init_params.is_object() {
+            let init_params = js_sys::JSON::stringify(&init_params)
+                .map_err(|_| anyhow!("Invalid init params"))?;
+            serde_json::from_str(&String::from(init_params))?
+        } else {
+            Value::Object(Default::default())
+        };
+
+        let public_key = match get(params, "publicKey").as_string() {
+            Some(public_key) => {
+                let bytes = hex::decode(public_key.trim())?;
+                if bytes.len() != 32 {
+                    return Err(anyhow!("Invalid public key"));
+                }
+                Some(UInt256::from_slice(&bytes))
+            }
+            None => None,
+        };
+
+        let mut libraries = Vec::new();
+        let items = get(params, "libraries");
+        if js_sys::Array::is_array(&items) {
+            for item in js_sys::Array::from(&items).iter() {
+                let boc = get(&item, "boc")
+                    .as_string()
+                    .ok_or_else(|| anyhow!("Library BOC is required"))?;
+                libraries.push((boc, get(&item, "public").as_bool().unwrap_or_default()));
+            }
+        }
+
+        let special = get(params, "special");
+        let special = if special.is_object() {
+            Some((
+                get(&special, "tick").as_bool().unwrap_or_default(),
+                get(&special, "tock").as_bool().unwrap_or_default(),
+            ))
+        } else {
+            None
+        };
+
+        let workchain = match parse_integer(&get(params, "workchain"), "workchain")? {
+            Some(workchain) => i8::try_from(workchain)?,
+            None => 0,
+        };
+        let split_depth = match parse_integer(&get(params, "splitDepth"), "split depth")? {
+            Some(depth) => Some(u32::try_from(depth)?),
+            None => None,
+        };
+
+        Ok(Self {
+            code,
+            abi: get(params, "abi").as_string(),
+            init_params,
+            public_key,
+            data: get(params, "data").as_string(),
+            libraries,
+            split_depth,
+            special,
+            workchain,
+        })
+    }
+}
+
+fn parse_integer(value: &JsValue, name: &str) -> Result<Option<i64>> {
+    match value.as_f64() {
+        Some(value) if value.fract() == 0.0 && value.abs() <= i32::MAX as f64 => {
+            Ok(Some(value as i64))
+        }
+        Some(_) => Err(anyhow!("Invalid {}", name)),
+        None => Ok(None),
+    }
+}
+
+fn make_initial_data(abi: &str, init_params: &Value, public_key: Option<UInt256>) -> Result<Cell> {
+    let contract = ton_abi::Contract::load(abi.as_bytes())?;
+    let raw = serde_json::from_str::<Value>(abi)?;
+    let public_key = public_key.unwrap_or_default();
+
+    if has_init_fields(&raw) {
+        let init_fields = raw["fields"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter(|field| field["init"].as_bool().unwrap_or_default())
+            .filter_map(|field| field["name"].as_str())
+            .collect::<Vec<_>>();
+
+        let mut tokens = Vec::with_capacity(contract.fields.len());
+        for field in &contract.fields {
+            let value = match field.name.as_str() {
+                "_pubkey" => TokenValue::Uint(ton_abi::Uint {
+                    number: num_bigint::BigUint::from_bytes_be(public_key.as_slice()),
+                    size: 256,
+                }),
+                name if init_fields.contains(&name) => {
+                    tokenize_params(std::slice::from_ref(field), init_params)?
+                        .remove(0)
+                        .value
+                }
+                _ => TokenValue::default_value(&field.kind),
+            };
+            tokens.push(ton_abi::Token::new(&field.name, value));
+        }
+
+        let builder =
+            TokenValue::pack_values_into_chain(&tokens, Vec::new(), &contract.abi_version)?;
+        return builder.into_cell();
+    }
+
+    let mut map = HashmapE::with_bit_len(64);
+    map.set_builder(
+        make_key(0)?,
+        &BuilderData::from_slice(&SliceData::from_raw(public_key.as_slice().to_vec(), 256)),
+    )?;
+
+    let items = contract
+        .data
+        .values()
+        .map(|item| (item.key, &item.value))
+        .collect::<BTreeMap<_, _>>();
+    for (key, param) in items {
+        let tokens = tokenize_params(std::slice::from_ref(param), init_params)?;
+        let builder =
+            TokenValue::pack_values_into_chain(&tokens, Vec::new(), &contract.abi_version)?;
+        map.set_builder(make_key(key)?, &builder)?;
+    }
+
+    let mut builder = BuilderData::new();
+    match map.data() {
+        Some(root) => {
+            builder.append_bit_one()?;
+            builder.checked_append_reference(root.clone())?;
+        }
+        None => {
+            builder.append_bit_zero()?;
+        }
+    }
+    builder.into_cell()
+}
+
+/// Tokenizes only the specified parameters of the JSON object.
+fn tokenize_params(params: &[ton_abi::Param], values: &Value) -> Result<Vec<ton_abi::Token>> {
+    let mut subset = Map::new();
+    for param in params {
+        let value = values
+            .get(&param.name)
+            .ok_or_else(|| anyhow!("Init param `{}` is required", param.name))?;
+        subset.insert(param.name.clone(), value.clone());
+    }
+    ton_abi::token::Tokenizer::tokenize_all_params(params, &Value::Object(subset))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ABI_2_3: &str = r#"{
+        "ABI version": 2,
+        "version": "2.3",
+        "header": ["time"],
+        "functions": [],
+        "events": [],
+        "data": [
+            { "key": 1, "name": "owner", "type": "address" },
+            { "key": 2, "name": "nonce", "type": "uint32" }
+        ],
+        "fields": [
+            { "name": "_pubkey", "type": "uint256" },
+            { "name": "_timestamp", "type": "uint64" },
+            { "name": "_constructorFlag", "type": "bool" },
+            { "name": "owner", "type": "address" },
+            { "name": "nonce", "type": "uint32" }
+        ]
+    }"#;
+
+    const ABI_2_4: &str = r#"{
+        "ABI version": 2,
+        "version": "2.4",
+        "header": ["time"],
+        "functions": [],
+        "events": [],
+        "fields": [
+            { "name": "_pubkey", "type": "uint256", "init": true },
+            { "name": "_timestamp", "type": "uint64" },
+            { "name": "_constructorFlag", "type": "bool" },
+            { "name": "owner", "type": "address", "init": true },
+            { "name": "nonce", "type": "uint32", "init": true },
+            { "name": "counter", "type": "uint32" }
+        ]
+    }"#;
+
+    fn owner() -> String {
+        format!("0:{}", "33".repeat(32))
+    }
+
+    fn pack_fields(abi: &str, values: &[(&str, TokenValue)]) -> Cell {
+        let contract = ton_abi::Contract::load(abi.as_bytes()).unwrap();
+        let tokens = contract
+            .fields
+            .iter()
+            .map(|field| {
+                let value = values
+                    .iter()
+                    .find(|(name, _)| *name == field.name)
+                    .map(|(_, value)| value.clone())
+                    .unwrap_or_else(|| TokenValue::default_value(&field.kind));
+                ton_abi::Token::new(&field.name, value)
+            })
+            .collect::<Vec<_>>();
+        TokenValue::pack_values_into_chain(&tokens, Vec::new(), &contract.abi_version)
+            .unwrap()
+            .into_cell()
+            .unwrap()
+    }
+
+    #[test]
+    fn deployed_fields() {
+        let data = pack_fields(
+            ABI_2_3,
+            &[
+                ("_pubkey", TokenValue::Uint(ton_abi::Uint::new(0xab, 256))),
+                (
+                    "_timestamp",
+                    TokenValue::Uint(ton_abi::Uint::new(1700000000000, 64)),
+                ),
+                ("_constructorFlag", TokenValue::Bool(true)),
+                ("nonce", TokenValue::Uint(ton_abi::Uint::new(7, 32))),
+            ],
+        );
+
+        let value = decode_data(ABI_2_3, data).unwrap();
+        assert_eq!(value["layout"], "fields");
+        assert_eq!(value["initialized"], true);
+        assert_eq!(value["pubkey"], format!("{:064x}", 0xab));
+        assert_eq!(value["timestamp"], "1700000000000");
+        assert_eq!(value["constructorFlag"], true);
+        assert_eq!(value["fields"]["nonce"], "7");
+        assert!(value["fields"].get("_pubkey").is_none());
+    }
+
+    #[test]
+    fn init_data_dictionary() {
+        let mut map = HashmapE::with_bit_len(64);
+        map.set_builder(
+            make_key(0).unwrap(),
+            &BuilderData::from_slice(&SliceData::from_raw(vec![0x11; 32], 256)),
+        )
+        .unwrap();
+        let mut nonce = BuilderData::new();
+        nonce.append_u32(7).unwrap();
+        map.set_builder(make_key(2).unwrap(), &nonce).unwrap();
+
+        let mut builder = BuilderData::new();
+        builder.append_bit_one().unwrap();
+        builder
+            .checked_append_reference(map.data().cloned().unwrap())
+            .unwrap();
+
+        let value = decode_data(ABI_2_3, builder.into_cell().unwrap()).unwrap();
+        assert_eq!(value["layout"], "initData");
+        assert_eq!(value["initialized"], false);
+        assert_eq!(value["pubkey"], "11".repeat(32));
+        assert_eq!(value["fields"]["nonce"], "7");
+        assert_eq!(value["fields"]["owner"], Value::Null);
+
+        // An empty dictionary
+        let mut builder = BuilderData::new();
+        builder.append_bit_zero().unwrap();
+        let value = decode_data(ABI_2_3, builder.into_cell().unwrap()).unwrap();
+        assert_eq!(value["layout"], "initData");
+        assert_eq!(value["pubkey"], Value::Null);
+    }
+
+    #[test]
+    fn initial_data_fields() {
+        let public_key = UInt256::from_slice(&[0x11; 32]);
+        let init_params = json!({ "owner": owner(), "nonce": 7 });
+        let data = make_initial_data(ABI_2_4, &init_params, Some(public_key)).unwrap();
+
+        let value = decode_data(ABI_2_4, data).unwrap();
+        assert_eq!(value["layout"], "fields");
+        assert_eq!(value["initialized"], false);
+        assert_eq!(value["constructorFlag"], false);
+        assert_eq!(value["pubkey"], "11".repeat(32));
+        assert_eq!(value["timestamp"], "0");
+        assert_eq!(value["fields"]["owner"], owner());
+        assert_eq!(value["fields"]["nonce"], "7");
+        assert_eq!(value["fields"]["counter"], "0");
+
+        // All init fields are required, the rest get default values
+        assert!(make_initial_data(ABI_2_4, &json!({ "owner": owner() }), None).is_err());
+        let data = make_initial_data(ABI_2_4, &init_params, None).unwrap();
+        assert_eq!(
+            decode_data(ABI_2_4, data).unwrap()["pubkey"],
+            "00".repeat(32)
+        );
+    }
+
+    #[test]
+    fn initial_data_dictionary() {
+        let public_key = UInt256::from_slice(&[0x11; 32]);
+        let init_params = json!({ "owner": owner(), "nonce": 7 });
+        let data = make_initial_data(ABI_2_3, &init_params, Some(public_key)).unwrap();
+
+        let value = decode_data(ABI_2_3, data).unwrap();
+        assert_eq!(value["layout"], "initData");
+        assert_eq!(value["initialized"], false);
+        assert_eq!(value["pubkey"], "11".repeat(32));
+        assert_eq!(value["fields"]["owner"], owner());
+        assert_eq!(value["fields"]["nonce"], "7");
+
+        assert!(make_initial_data(ABI_2_3, &json!({ "nonce": 7 }), None).is_err());
+        let data = make_initial_data(ABI_2_3, &init_params, None).unwrap();
+        assert_eq!(
+            decode_data(ABI_2_3, data).unwrap()["pubkey"],
+            "00".repeat(32)
+        );
+    }
+}
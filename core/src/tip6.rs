@@ -0,0 +1,288 @@
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Result};
+use once_cell::sync::OnceCell;
+use shared::*;
+use wasm_bindgen::prelude::*;
+
+struct KnownInterface {
+    name: &'static str,
+    /// Function signatures in the `abi-parser` syntax, one per line.
+    /// Functions with an explicit `functionID` have it after the name.
+    functions: &'static str,
+}
+
+pub const TIP6_SID: &str = "TIP-6 SID";
+
+const KNOWN_INTERFACES: &[KnownInterface] = &[
+    KnownInterface {
+        name: TIP6_SID,
+        functions: "supportsInterface(uint32,uint32)(bool)",
+    },
+    KnownInterface {
+        name: "TIP-3.1 Token Root",
+        functions: "
+            name(uint32)(string)
+            symbol(uint32)(string)
+            decimals(uint32)(uint8)
+            totalSupply(uint32)(uint128)
+            walletCode(uint32)(cell)
+            acceptBurn#192b51b1(uint128,address,address,address,cell)()
+        ",
+    },
+    KnownInterface {
+        name: "TIP-3.1 Token Wallet",
+        functions: "
+            root(uint32)(address)
+            balance(uint32)(uint128)
+            walletCode(uint32)(cell)
+            acceptTransfer#67a0b95f(uint128,address,address,bool,cell)()
+            acceptMint#4384f298(uint128,address,bool,cell)()
+        ",
+    },
+    KnownInterface {
+        name: "TIP-4.1 Collection",
+        functions: "
+            totalSupply(uint32)(uint128)
+            nftCode(uint32)(cell)
+            nftCodeHash(uint32)(uint256)
+            nftAddress(uint32,uint256)(address)
+        ",
+    },
+    KnownInterface {
+        name: "TIP-4.1 NFT",
+        functions: "
+            getInfo(uint32)(uint256,address,address,address)
+            changeOwner(address,address,map(address,(uint128,cell)))()
+            changeManager(address,address,map(address,(uint128,cell)))()
+            transfer(address,address,map(address,(uint128,cell)))()
+        ",
+    },
+    KnownInterface {
+        name: "TIP-4.2 JSON Metadata",
+        functions: "getJson(uint32)(string)",
+    },
+    KnownInterface {
+        name: "TIP-4.3 Index",
+        functions: "
+            getInfo(uint32)(address,address,address)
+            destruct(address)()
+        ",
+    },
+];
+
+#[wasm_bindgen(typescript_custom_section)]
+const INTERFACES: &str = r#"
+export type InterfaceId = {
+    id: number,
+    functions: FunctionEntry[],
+};
+
+export type DetectedInterface = {
+    name: string,
+    id: number,
+    implementation: 'full' | 'partial' | 'none',
+    missing: string[],
+};
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "InterfaceId")]
+    pub type InterfaceId;
+
+    #[wasm_bindgen(typescript_type = "DetectedInterface")]
+    pub type DetectedInterface;
+}
+
+/// Computes the TIP-6 interface id from a JSON ABI (or its subset with only `functions`)
+/// or from function signatures in the `abi-parser` syntax, one per line.
+#[wasm_bindgen(js_name = "computeInterfaceId")]
+pub fn compute_interface_id(input: &str) -> Result<InterfaceId, JsValue> {
+    let functions = parse_functions(input).handle_error()?;
+
+    let entries = js_sys::Array::new();
+    let mut id = 0;
+    for (name, function_id) in &functions {
+        id ^= function_id;
+        entries.push(
+            &ObjectBuilder::new()
+                .set("name", name.as_str())
+                .set("id", *function_id)
+                .build(),
+        );
+    }
+
+    Ok(ObjectBuilder::new()
+        .set("id", id)
+        .set("functions", entries)
+        .build()
+        .unchecked_into())
+}
+
+/// Checks which of the known standard interfaces the contract implements.
+///
+/// A method is considered implemented only when both its name and its id match.
+#[wasm_bindgen(js_name = "detectInterfaces")]
+pub fn detect_interfaces(abi: &str) -> Result<Vec<DetectedInterface>, JsValue> {
+    let contract = ton_abi::Contract::load(abi.as_bytes()).handle_error()?;
+
+    Ok(match_interfaces(&contract)
+        .into_iter()
+        .map(|interface| {
+            ObjectBuilder::new()
+                .set("name", interface.name)
+                .set("id", interface.id)
+                .set("implementation", interface.implementation())
+                .set(
+                    "missing",
+                    interface
+                        .missing
+                        .iter()
+                        .map(|name| JsValue::from_str(name))
+                        .collect::<js_sys::Array>(),
+                )
+                .build()
+                .unchecked_into()
+        })
+        .collect())
+}
+
+pub struct InterfaceMatch {
+    pub name: &'static str,
+    pub id: u32,
+    /// Names and ids of the interface functions.
+    pub functions: Vec<(String, u32)>,
+    /// Names of the functions which are absent or have a different id.
+    pub missing: Vec<String>,
+}
+
+impl InterfaceMatch {
+    pub fn implementation(&self) -> &'static str {
+        match self.missing.len() {
+            0 => "full",
+            count if count == self.functions.len() => "none",
+            _ => "partial",
+        }
+    }
+}
+
+/// Matches the contract functions against each of the known interfaces.
+pub fn match_interfaces(contract: &ton_abi::Contract) -> Vec<InterfaceMatch> {
+    parsed_interfaces()
+        .iter()
+        .map(|interface| {
+            let missing = interface
+                .functions
+                .iter()
+                .filter(|(name, function_id)| {
+                    contract
+                        .functions
+                        .get(name)
+                        .map_or(true, |function| function.input_id != *function_id)
+                })
+                .map(|(name, _)| name.clone())
+                .collect();
+
+            InterfaceMatch {
+                name: interface.name,
+                id: interface.id,
+                functions: interface.functions.clone(),
+                missing,
+            }
+        })
+        .collect()
+}
+
+struct ParsedInterface {
+    name: &'static str,
+    id: u32,
+    functions: Vec<(String, u32)>,
+}
+
+fn parsed_interfaces() -> &'static [ParsedInterface] {
+    static INSTANCE: OnceCell<Vec<ParsedInterface>> = OnceCell::new();
+    INSTANCE.get_or_init(|| {
+        KNOWN_INTERFACES
+            .iter()
+            .map(|interface| {
+                let functions = parse_signatures(interface.functions).expect("Shouldn't fail");
+                let id = functions
+                    .iter()
+                    .fold(0, |id, (_, function_id)| id ^ function_id);
+                ParsedInterface {
+                    name: interface.name,
+                    id,
+                    functions,
+                }
+            })
+            .collect()
+    })
+}
+
+fn parse_functions(input: &str) -> Result<Vec<(String, u32)>> {
+    let input = input.trim();
+    if !input.starts_with('{') {
+        return parse_signatures(input);
+    }
+
+    let mut raw = serde_json::from_str::<serde_json::Value>(input)?;
+    if let Some(object) = raw.as_object_mut() {
+        object
+            .entry("ABI version")
+            .or_insert_with(|| serde_json::json!(2));
+        object
+            .entry("version")
+            .or_insert_with(|| serde_json::json!("2.2"));
+        object
+            .entry("events")
+            .or_insert_with(|| serde_json::json!([]));
+    }
+    let contract = ton_abi::Contract::load(raw.to_string().as_bytes())?;
+
+    let mut functions = contract
+        .functions
+        .iter()
+        .map(|(name, function)| (name.clone(), function.input_id))
+        .collect::<Vec<_>>();
+    functions.sort_unstable();
+    Ok(functions)
+}
+
+fn parse_signatures(input: &str) -> Result<Vec<(String, u32)>> {
+    let mut functions = HashMap::new();
+    for line in input.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        match abi_parser::Entity::parse(line)? {
+            abi_parser::Entity::Function(function) => {
+                functions.insert(function.name, function.input_id);
+            }
+            _ => return Err(anyhow!("Expected a function signature: {line}")),
+        }
+    }
+
+    let mut functions = functions.into_iter().collect::<Vec<_>>();
+    functions.sort_unstable();
+    Ok(functions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_interface_ids() {
+        let ids = parsed_interfaces()
+            .iter()
+            .map(|interface| (interface.name, interface.id))
+            .collect::<HashMap<_, _>>();
+        assert_eq!(ids.len(), KNOWN_INTERFACES.len());
+
+        // Published in the standards
+        assert_eq!(ids[TIP6_SID], 0x3204ec29);
+        assert_eq!(ids["TIP-3.1 Token Root"], 0x4371d8ed);
+        assert_eq!(ids["TIP-3.1 Token Wallet"], 0x4f479fa3);
+        assert_eq!(ids["TIP-4.1 Collection"], 0x1217aaab);
+        assert_eq!(ids["TIP-4.1 NFT"], 0x78084f7e);
+        assert_eq!(ids["TIP-4.2 JSON Metadata"], 0x24d7d5f5);
+    }
+}
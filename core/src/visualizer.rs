@@ -1,14 +1,19 @@
+use std::collections::hash_map;
+use std::collections::HashMap;
 use std::fmt::Write;
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+use ton_block::Deserializable;
+use ton_types::{Cell, CellType, UInt256};
 use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
 
 use shared::*;
 
 #[wasm_bindgen]
 pub fn visualize(boc: &str) -> Result<String, JsValue> {
-    let boc = base64::decode(boc).handle_error()?;
-    let cells = ton_types::deserialize_cells_tree(&mut boc.as_slice()).handle_error()?;
+    let (_, cells) = crate::boc::parse_boc(boc).handle_error()?;
 
     let mut result = String::new();
     for cell in cells {
@@ -17,3 +22,757 @@ pub fn visualize(boc: &str) -> Result<String, JsValue> {
 
     Ok(result)
 }
+
+/// Returns the cell DAG of the BOC as JSON with deduplicated cells.
+///
+/// Cells are listed in the order of the first visit and reference each other by index.
+#[wasm_bindgen(js_name = "visualizeTree")]
+pub fn visualize_tree(boc: &str) -> Result<String, JsValue> {
+    let (_, cells) = crate::boc::parse_boc(boc).handle_error()?;
+    let tree = CellTree::build(&cells).handle_error()?;
+    Ok(tree.to_json().to_string())
+}
+
+#[wasm_bindgen(typescript_custom_section)]
+const BUILT_CELL: &str = r#"
+export type BuiltCell = {
+    boc: string,
+    hash: string,
+};
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "BuiltCell")]
+    pub type BuiltCell;
+}
+
+/// Builds a BOC either from the Fift-like cell notation or from the `visualizeTree` output.
+#[wasm_bindgen(js_name = "buildCell")]
+pub fn build_cell(input: &str) -> Result<BuiltCell, JsValue> {
+    let roots = match serde_json::from_str::<Value>(input) {
+        Ok(json) if json.get("cells").is_some() => {
+            CellTree::roots_from_json(&json).handle_error()?
+        }
+        _ => vec![abi_parser::cell::build_cell(input).handle_error()?],
+    };
+    let hash = match roots.first() {
+        Some(root) => root.repr_hash(),
+        None => return Err("No root cells").handle_error(),
+    };
+
+    let boc = crate::boc::write_boc(&roots, Default::default()).handle_error()?;
+
+    Ok(ObjectBuilder::new()
+        .set("boc", base64::encode(boc))
+        .set("hash", hash.to_hex_string())
+        .build()
+        .unchecked_into())
+}
+
+#[wasm_bindgen(typescript_custom_section)]
+const CELL_GRAPH_OPTIONS: &str = r#"
+export type CellGraphOptions = {
+    format?: 'dot' | 'mermaid',
+    label?: 'hash' | 'bits' | 'field',
+    layout?: string,
+};
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "CellGraphOptions | undefined")]
+    pub type OptionalCellGraphOptions;
+}
+
+/// Exports the cell DAG of the BOC as a Graphviz DOT or Mermaid graph.
+///
+/// Shared cells are rendered as a single node, exotic cells are highlighted.
+/// The `field` label uses names of the `layout` fields (see `overlayLayout`) stored in each
+/// cell of the first root and falls back to the hash prefix.
+#[wasm_bindgen(js_name = "exportCellGraph")]
+pub fn export_cell_graph(boc: &str, options: OptionalCellGraphOptions) -> Result<String, JsValue> {
+    let mut options = CellGraphOptions::from_js(&options).handle_error()?;
+    let (_, cells) = crate::boc::parse_boc(boc).handle_error()?;
+    let tree = CellTree::build(&cells).handle_error()?;
+    if let Some(layout) = &options.layout {
+        options.fields = tree.field_names(layout).handle_error()?;
+    }
+    Ok(match options.format {
+        GraphFormat::Dot => tree.to_dot(&options),
+        GraphFormat::Mermaid => tree.to_mermaid(&options),
+    })
+}
+
+/// Reports cell statistics of the BOC and the duplicated subtrees which are
+/// stored only once thanks to deduplication.
+///
+/// Each duplicated subtree is reported once with the cells which are not shared
+/// with other parts of the tree, so nested duplicates are not counted twice.
+///
+/// When `config` is specified, also estimates the storage fee for the next year
+/// in each workchain under the current prices.
+#[wasm_bindgen(js_name = "analyzeBoc")]
+pub fn analyze_boc(boc: &str, config: Option<String>) -> Result<String, JsValue> {
+    const MAX_DUPLICATES: usize = 10;
+    const SECONDS_PER_YEAR: u32 = 365 * 24 * 60 * 60;
+
+    let (header, cells) = crate::boc::parse_boc(boc).handle_error()?;
+    let tree = CellTree::build(&cells).handle_error()?;
+    let expanded = tree.expanded_sizes();
+    let occurrences = tree.occurrences();
+    let exclusive = tree.exclusive_sizes();
+
+    let unique_bits = tree
+        .nodes
+        .iter()
+        .map(|node| node.cell.bit_length() as u128)
+        .sum::<u128>();
+    let (total_cells, total_bits) =
+        tree.roots
+            .iter()
+            .fold((0u128, 0u128), |(cells, bits), root| {
+                let (root_cells, root_bits) = expanded[*root];
+                (
+                    cells.saturating_add(root_cells),
+                    bits.saturating_add(root_bits),
+                )
+            });
+    let max_depth = cells.iter().map(|cell| cell.repr_depth()).max();
+
+    let mut duplicates = tree
+        .nodes
+        .iter()
+        .enumerate()
+        .filter(|(_, node)| node.usages > 1)
+        .map(|(index, node)| {
+            let (cells, bits) = exclusive[index];
+            let copies = occurrences[index] - 1;
+            (
+                index,
+                node,
+                cells,
+                bits,
+                cells.saturating_mul(copies),
+                bits.saturating_mul(copies),
+            )
+        })
+        .collect::<Vec<_>>();
+    duplicates.sort_by(|a, b| b.4.cmp(&a.4).then(b.5.cmp(&a.5)));
+    let duplicates = duplicates
+        .into_iter()
+        .take(MAX_DUPLICATES)
+        .map(|(index, node, cells, bits, saved_cells, saved_bits)| {
+            serde_json::json!({
+                "index": index,
+                "hash": node.hash.to_hex_string(),
+                "usages": node.usages,
+                "occurrences": occurrences[index].to_string(),
+                "cells": cells.to_string(),
+                "bits": bits.to_string(),
+                "saved_cells": saved_cells.to_string(),
+                "saved_bits": saved_bits.to_string(),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let storage_fees = match config {
+        Some(config) => {
+            let config = ton_block::ConfigParams::construct_from_cell(
+                crate::boc::parse_cell(&config).handle_error()?,
+            )
+            .handle_error()?;
+            let now = (js_sys::Date::now() / 1000.0) as u32;
+
+            [0i32, -1]
+                .iter()
+                .map(|workchain| {
+                    let calculator = crate::microwave::StorageFeeCalculator::new(
+                        &config,
+                        *workchain == ton_block::MASTERCHAIN_ID,
+                    )?;
+                    let fee = calculator.compute_ahead(
+                        tree.nodes.len() as u128,
+                        unique_bits,
+                        now,
+                        SECONDS_PER_YEAR,
+                    );
+                    Ok(serde_json::json!({
+                        "workchain": workchain,
+                        "fee_per_year": fee.to_string(),
+                    }))
+                })
+                .collect::<Result<Vec<_>>>()
+                .handle_error()?
+                .into()
+        }
+        None => Value::Null,
+    };
+
+    Ok(serde_json::json!({
+        "boc_size": header.total_size,
+        "roots": tree.roots.len(),
+        "unique_cells": tree.nodes.len(),
+        "unique_bits": unique_bits.to_string(),
+        "total_cells": total_cells.to_string(),
+        "total_bits": total_bits.to_string(),
+        "max_depth": max_depth,
+        "duplicates": duplicates,
+        "storage_fees": storage_fees,
+    })
+    .to_string())
+}
+
+pub struct CellTree {
+    pub roots: Vec<usize>,
+    pub nodes: Vec<CellNode>,
+}
+
+pub struct CellNode {
+    pub cell: Cell,
+    pub hash: UInt256,
+    pub references: Vec<usize>,
+    /// The number of references to this cell from other cells and roots.
+    pub usages: usize,
+}
+
+impl CellTree {
+    /// Rebuilds root cells from the output of [`CellTree::to_json`].
+    pub fn roots_from_json(json: &Value) -> Result<Vec<Cell>> {
+        fn build(
+            nodes: &[Value],
+            cells: &mut Vec<Option<Cell>>,
+            visiting: &mut Vec<bool>,
+            index: usize,
+        ) -> Result<Cell> {
+            let node = nodes
+                .get(index)
+                .ok_or_else(|| anyhow!("Cell index out of range: {index}"))?;
+            if let Some(cell) = &cells[index] {
+                return Ok(cell.clone());
+            }
+            if std::mem::replace(&mut visiting[index], true) {
+                return Err(anyhow!("Cyclic reference to cell {index}"));
+            }
+
+            let hex = node["hex"]
+                .as_str()
+                .ok_or_else(|| anyhow!("Cell {index} has no `hex` data"))?;
+            let mut builder = abi_parser::cell::parse_hex_slice(hex)?;
+            for child in node["references"].as_array().into_iter().flatten() {
+                let child = child
+                    .as_u64()
+                    .ok_or_else(|| anyhow!("Invalid reference in cell {index}"))?;
+                builder.checked_append_reference(build(nodes, cells, visiting, child as usize)?)?;
+            }
+            match node["type"].as_str() {
+                None | Some("ordinary") => {}
+                Some(name) => builder.set_type(parse_cell_type(name)?),
+            }
+
+            let cell = builder.into_cell()?;
+            cells[index] = Some(cell.clone());
+            Ok(cell)
+        }
+
+        let nodes = json["cells"]
+            .as_array()
+            .ok_or_else(|| anyhow!("Expected a `cells` array"))?;
+        let mut cells = vec![None; nodes.len()];
+        let mut visiting = vec![false; nodes.len()];
+
+        json["roots"]
+            .as_array()
+            .ok_or_else(|| anyhow!("Expected a `roots` array"))?
+            .iter()
+            .map(|root| {
+                let index = root.as_u64().ok_or_else(|| anyhow!("Invalid root index"))?;
+                build(nodes, &mut cells, &mut visiting, index as usize)
+            })
+            .collect()
+    }
+
+    pub fn build(roots: &[Cell]) -> Result<Self> {
+        let mut builder = CellTreeBuilder::default();
+        let roots = roots
+            .iter()
+            .map(|cell| builder.visit(cell))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self {
+            roots,
+            nodes: builder.nodes,
+        })
+    }
+
+    pub fn to_json(&self) -> Value {
+        serde_json::json!({
+            "roots": self.roots,
+            "cells": self.nodes.iter().enumerate().map(|(index, node)| {
+                let cell = &node.cell;
+                serde_json::json!({
+                    "index": index,
+                    "hash": node.hash.to_hex_string(),
+                    "depth": cell.repr_depth(),
+                    "level_mask": cell.level_mask().mask(),
+                    "type": cell_type_name(cell.cell_type()),
+                    "bits": cell.bit_length(),
+                    "hex": cell_data_hex(cell),
+                    "binary": cell_data_binary(cell),
+                    "references": node.references,
+                    "usages": node.usages,
+                    "exotic": decode_exotic(cell),
+                })
+            }).collect::<Vec<_>>(),
+        })
+    }
+}
+
+impl CellTree {
+    /// Computes the number of cells and bits of each subtree without deduplication.
+    pub fn expanded_sizes(&self) -> Vec<(u128, u128)> {
+        fn visit(
+            tree: &CellTree,
+            index: usize,
+            sizes: &mut Vec<Option<(u128, u128)>>,
+        ) -> (u128, u128) {
+            if let Some(size) = sizes[index] {
+                return size;
+            }
+
+            let node = &tree.nodes[index];
+            let mut size = (1u128, node.cell.bit_length() as u128);
+            for child in &node.references {
+                let (cells, bits) = visit(tree, *child, sizes);
+                size = (size.0.saturating_add(cells), size.1.saturating_add(bits));
+            }
+            sizes[index] = Some(size);
+            size
+        }
+
+        let mut sizes = vec![None; self.nodes.len()];
+        (0..self.nodes.len())
+            .map(|index| visit(self, index, &mut sizes))
+            .collect()
+    }
+
+    /// Returns how many times each cell occurs in the tree without deduplication.
+    pub fn occurrences(&self) -> Vec<u128> {
+        fn visit(tree: &CellTree, index: usize, visited: &mut Vec<bool>, order: &mut Vec<usize>) {
+            if std::mem::replace(&mut visited[index], true) {
+                return;
+            }
+            for child in &tree.nodes[index].references {
+                visit(tree, *child, visited, order);
+            }
+            order.push(index);
+        }
+
+        let mut visited = vec![false; self.nodes.len()];
+        let mut order = Vec::with_capacity(self.nodes.len());
+        for root in &self.roots {
+            visit(self, *root, &mut visited, &mut order);
+        }
+
+        // Parents go before their children in the reversed post-order
+        let mut occurrences = vec![0u128; self.nodes.len()];
+        for root in &self.roots {
+            occurrences[*root] += 1;
+        }
+        for index in order.into_iter().rev() {
+            let count = occurrences[index];
+            for child in &self.nodes[index].references {
+                occurrences[*child] = occurrences[*child].saturating_add(count);
+            }
+        }
+        occurrences
+    }
+
+    /// Returns the number of cells and bits of each subtree, excluding the children
+    /// which are referenced from other cells as well.
+    pub fn exclusive_sizes(&self) -> Vec<(u128, u128)> {
+        fn visit(
+            tree: &CellTree,
+            index: usize,
+            sizes: &mut Vec<Option<(u128, u128)>>,
+        ) -> (u128, u128) {
+            if let Some(size) = sizes[index] {
+                return size;
+            }
+
+            let node = &tree.nodes[index];
+            let mut size = (1u128, node.cell.bit_length() as u128);
+            for child in &node.references {
+                if tree.nodes[*child].usages > 1 {
+                    continue;
+                }
+                let (cells, bits) = visit(tree, *child, sizes);
+                size = (size.0 + cells, size.1 + bits);
+            }
+            sizes[index] = Some(size);
+            size
+        }
+
+        let mut sizes = vec![None; self.nodes.len()];
+        (0..self.nodes.len())
+            .map(|index| visit(self, index, &mut sizes))
+            .collect()
+    }
+
+    /// Maps hashes of the first root cells to the names of the layout fields stored in them.
+    ///
+    /// Referenced cells (e.g. `cell` or `map` fields) are named after their field.
+    pub fn field_names(&self, layout: &str) -> Result<HashMap<String, String>> {
+        let root = *self
+            .roots
+            .first()
+            .ok_or_else(|| anyhow!("BOC has no roots"))?;
+        let overlay = crate::overlay::overlay(self.nodes[root].cell.clone(), layout)?;
+
+        let mut names = HashMap::<usize, Vec<&str>>::new();
+        for range in overlay["ranges"].as_array().into_iter().flatten() {
+            let path = range["path"].as_str().unwrap_or_default();
+            if path.ends_with(".flag") || path.ends_with(".length") {
+                continue;
+            }
+
+            let mut index = root;
+            let cell_path = range["cell"].as_str().unwrap_or_default();
+            let child = range["ref"].as_u64().map(|child| child as usize);
+            for i in cell_path
+                .split('.')
+                .filter(|i| !i.is_empty())
+                .map(|i| i.parse::<usize>().ok())
+                .chain(child.map(Some))
+            {
+                index = match i.and_then(|i| self.nodes[index].references.get(i)) {
+                    Some(child) => *child,
+                    None => return Err(anyhow!("Invalid overlay cell path: {cell_path}")),
+                };
+            }
+
+            let entry = names.entry(index).or_default();
+            if !entry.contains(&path) {
+                entry.push(path);
+            }
+        }
+
+        Ok(names
+            .into_iter()
+            .map(|(index, names)| (self.nodes[index].hash.to_hex_string(), names.join(", ")))
+            .collect())
+    }
+
+    pub fn to_dot(&self, options: &CellGraphOptions) -> String {
+        let mut result =
+            String::from("digraph cells {\n    node [shape=box, fontname=monospace];\n");
+        for (index, node) in self.nodes.iter().enumerate() {
+            let mut attributes = format!("label=\"{}\"", escape_label(&options.label(node)));
+            if node.cell.cell_type() != CellType::Ordinary {
+                attributes.push_str(", style=\"dashed,filled\", fillcolor=lightyellow");
+            }
+            if self.roots.contains(&index) {
+                attributes.push_str(", penwidth=2");
+            }
+            let _ = writeln!(&mut result, "    c{} [{}];", index, attributes);
+        }
+        for (index, node) in self.nodes.iter().enumerate() {
+            for (i, child) in node.references.iter().enumerate() {
+                let _ = writeln!(
+                    &mut result,
+                    "    c{} -> c{} [label=\"{}\"];",
+                    index, child, i
+                );
+            }
+        }
+        result.push_str("}\n");
+        result
+    }
+
+    pub fn to_mermaid(&self, options: &CellGraphOptions) -> String {
+        let mut result = String::from("graph TD\n");
+        for (index, node) in self.nodes.iter().enumerate() {
+            let label = options.label(node).replace('"', "#quot;");
+            if node.cell.cell_type() != CellType::Ordinary {
+                let _ = writeln!(&mut result, "    c{}{{{{\"{}\"}}}}:::exotic", index, label);
+            } else if self.roots.contains(&index) {
+                let _ = writeln!(&mut result, "    c{}([\"{}\"])", index, label);
+            } else {
+                let _ = writeln!(&mut result, "    c{}[\"{}\"]", index, label);
+            }
+        }
+        for (index, node) in self.nodes.iter().enumerate() {
+            for (i, child) in node.references.iter().enumerate() {
+                let _ = writeln!(&mut result, "    c{} -->|{}| c{}", index, i, child);
+            }
+        }
+        result.push_str("    classDef exotic stroke-dasharray: 5 5, fill: #ffffe0\n");
+        result
+    }
+}
+
+#[derive(Default)]
+pub struct CellGraphOptions {
+    pub format: GraphFormat,
+    pub label: GraphLabel,
+    pub layout: Option<String>,
+    /// Field names by cell hash.
+    pub fields: HashMap<String, String>,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum GraphFormat {
+    Dot,
+    Mermaid,
+}
+
+impl Default for GraphFormat {
+    fn default() -> Self {
+        Self::Dot
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum GraphLabel {
+    Hash,
+    Bits,
+    Field,
+}
+
+impl Default for GraphLabel {
+    fn default() -> Self {
+        Self::Hash
+    }
+}
+
+impl CellGraphOptions {
+    fn from_js(options: &JsValue) -> Result<Self> {
+        let mut result = Self::default();
+        if options.is_undefined() || options.is_null() {
+            return Ok(result);
+        }
+
+        let get = |key: &str| {
+            js_sys::Reflect::get(options, &JsValue::from_str(key)).unwrap_or(JsValue::UNDEFINED)
+        };
+
+        if let Some(format) = get("format").as_string() {
+            result.format = match format.as_str() {
+                "dot" => GraphFormat::Dot,
+                "mermaid" => GraphFormat::Mermaid,
+                _ => return Err(anyhow!("Unknown graph format")),
+            };
+        }
+        if let Some(label) = get("label").as_string() {
+            result.label = match label.as_str() {
+                "hash" => GraphLabel::Hash,
+                "bits" => GraphLabel::Bits,
+                "field" => GraphLabel::Field,
+                _ => return Err(anyhow!("Unknown graph label")),
+            };
+        }
+        result.layout = get("layout").as_string();
+
+        Ok(result)
+    }
+
+    fn label(&self, node: &CellNode) -> String {
+        let hash = node.hash.to_hex_string();
+        let mut label = match self.label {
+            GraphLabel::Hash => hash[..8].to_string(),
+            GraphLabel::Bits => format!(
+                "{} bits, {} refs",
+                node.cell.bit_length(),
+                node.cell.references_count()
+            ),
+            GraphLabel::Field => match self.fields.get(&hash) {
+                Some(name) => name.clone(),
+                None => hash[..8].to_string(),
+            },
+        };
+        if node.cell.cell_type() != CellType::Ordinary {
+            label = format!("{} ({})", label, cell_type_name(node.cell.cell_type()));
+        }
+        label
+    }
+}
+
+fn escape_label(label: &str) -> String {
+    label.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[derive(Default)]
+struct CellTreeBuilder {
+    indices: HashMap<UInt256, usize>,
+    nodes: Vec<CellNode>,
+}
+
+impl CellTreeBuilder {
+    fn visit(&mut self, cell: &Cell) -> Result<usize> {
+        let hash = cell.repr_hash();
+        let index = match self.indices.entry(hash.clone()) {
+            hash_map::Entry::Occupied(entry) => {
+                let index = *entry.get();
+                self.nodes[index].usages += 1;
+                return Ok(index);
+            }
+            hash_map::Entry::Vacant(entry) => *entry.insert(self.nodes.len()),
+        };
+
+        self.nodes.push(CellNode {
+            cell: cell.clone(),
+            hash,
+            references: Vec::new(),
+            usages: 1,
+        });
+
+        let references = (0..cell.references_count())
+            .map(|i| {
+                let child = cell.reference(i)?;
+                self.visit(&child)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        self.nodes[index].references = references;
+
+        Ok(index)
+    }
+}
+
+pub fn cell_type_name(cell_type: CellType) -> &'static str {
+    match cell_type {
+        CellType::Ordinary => "ordinary",
+        CellType::PrunedBranch => "prunedBranch",
+        CellType::LibraryReference => "libraryReference",
+        CellType::MerkleProof => "merkleProof",
+        CellType::MerkleUpdate => "merkleUpdate",
+        _ => "unknown",
+    }
+}
+
+fn parse_cell_type(name: &str) -> Result<CellType> {
+    Ok(match name {
+        "ordinary" => CellType::Ordinary,
+        "prunedBranch" => CellType::PrunedBranch,
+        "libraryReference" => CellType::LibraryReference,
+        "merkleProof" => CellType::MerkleProof,
+        "merkleUpdate" => CellType::MerkleUpdate,
+        _ => return Err(anyhow!("Unknown cell type: {name}")),
+    })
+}
+
+/// Decodes the contents of an exotic cell, or returns `None` for ordinary cells.
+pub fn decode_exotic(cell: &Cell) -> Option<Value> {
+    let data = cell.data();
+    let bits = cell.bit_length();
+    let refs = cell.references_count();
+
+    let read_hash = |offset: usize| data.get(offset..offset + 32).map(hex::encode);
+    let read_depth = |offset: usize| {
+        data.get(offset..offset + 2)
+            .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]))
+    };
+
+    Some(match cell.cell_type() {
+        CellType::Ordinary => return None,
+        CellType::PrunedBranch => {
+            let level_mask = data.get(1).copied().unwrap_or_default();
+            let count = level_mask.count_ones() as usize;
+            let depths_offset = 2 + count * 32;
+            serde_json::json!({
+                "type": "prunedBranch",
+                "level": 8 - level_mask.leading_zeros(),
+                "level_mask": level_mask,
+                "hashes": (0..count).map(|i| read_hash(2 + i * 32)).collect::<Vec<_>>(),
+                "depths": (0..count).map(|i| read_depth(depths_offset + i * 2)).collect::<Vec<_>>(),
+                "valid": refs == 0
+                    && (1..=7).contains(&level_mask)
+                    && bits == 16 + count * (256 + 16),
+            })
+        }
+        CellType::LibraryReference => serde_json::json!({
+            "type": "libraryReference",
+            "hash": read_hash(1),
+            "valid": refs == 0 && bits == 8 + 256,
+        }),
+        CellType::MerkleProof => {
+            let hash = read_hash(1);
+            let depth = read_depth(33);
+            let valid = refs == 1
+                && bits == 8 + 256 + 16
+                && cell.reference(0).ok().map_or(false, |child| {
+                    hash.as_deref() == Some(child.hash(0).to_hex_string().as_str())
+                        && depth == Some(child.depth(0))
+                });
+            serde_json::json!({
+                "type": "merkleProof",
+                "virtual_hash": hash,
+                "virtual_depth": depth,
+                "valid": valid,
+            })
+        }
+        CellType::MerkleUpdate => {
+            let old_hash = read_hash(1);
+            let new_hash = read_hash(33);
+            let old_depth = read_depth(65);
+            let new_depth = read_depth(67);
+            let matches = |index: usize, hash: &Option<String>, depth: Option<u16>| {
+                cell.reference(index).ok().map_or(false, |child| {
+                    hash.as_deref() == Some(child.hash(0).to_hex_string().as_str())
+                        && depth == Some(child.depth(0))
+                })
+            };
+            let valid = refs == 2
+                && bits == 8 + 2 * (256 + 16)
+                && matches(0, &old_hash, old_depth)
+                && matches(1, &new_hash, new_depth);
+            serde_json::json!({
+                "type": "merkleUpdate",
+                "old_hash": old_hash,
+                "new_hash": new_hash,
+                "old_depth": old_depth,
+                "new_depth": new_depth,
+                "valid": valid,
+            })
+        }
+        _ => serde_json::json!({
+            "type": "unknown",
+            "valid": false,
+        }),
+    })
+}
+
+/// Formats cell data in the Fift hex notation (with the `_` completion tag when needed).
+pub fn cell_data_hex(cell: &Cell) -> String {
+    let bits = cell.bit_length();
+    let data = cell.data();
+
+    let mut result = hex::encode(&data[..(bits + 7) / 8]);
+    let rem = bits % 8;
+    if rem != 0 {
+        if rem != 4 {
+            // Append the completion tag to the last byte
+            let last = bits / 8;
+            let byte = (data[last] & !(0xff >> rem)) | (0x80 >> rem);
+            result.replace_range(last * 2.., &format!("{:02x}", byte));
+        }
+        if rem <= 4 {
+            result.pop();
+        }
+        if rem != 4 {
+            result.push('_');
+        }
+    }
+    result
+}
+
+pub fn cell_data_binary(cell: &Cell) -> String {
+    let data = cell.data();
+    (0..cell.bit_length())
+        .map(|i| {
+            if data[i / 8] & (0x80 >> (i % 8)) != 0 {
+                '1'
+            } else {
+                '0'
+            }
+        })
+        .collect()
+}
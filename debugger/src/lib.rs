@@ -7,6 +7,8 @@ use ton_block::{Deserializable, Serializable};
 use ton_executor::TransactionExecutor;
 use wasm_bindgen::prelude::*;
 
+mod local;
+
 #[wasm_bindgen(js_name = "execute")]
 pub fn execute(account: &str, tx: &str, config: &str) -> Result<JsValue, JsValue> {
     let mut root_cell = parse_cell(account)?;
@@ -15,6 +17,12 @@ pub fn execute(account: &str, tx: &str, config: &str) -> Result<JsValue, JsValue
     let in_msg = tx.read_in_msg().handle_error()?;
 
     let config = ton_block::ConfigParams::construct_from_base64(config).handle_error()?;
+    let is_masterchain = match account.get_addr() {
+        Some(addr) => addr.workchain_id() == ton_block::MASTERCHAIN_ID,
+        None => false,
+    };
+    let gas_price =
+        shared::fees::TransactionFees::gas_price(&config, is_masterchain).handle_error()?;
     let executor = ton_executor::OrdinaryTransactionExecutor::new(
         ton_executor::BlockchainConfig::with_config(config, 42).handle_error()?,
     );
@@ -83,7 +91,10 @@ pub fn execute(account: &str, tx: &str, config: &str) -> Result<JsValue, JsValue
     });
 
     Ok(ObjectBuilder::new()
-        .set("transaction", make_transaction(transaction)?)
+        .set(
+            "transaction",
+            make_transaction(transaction, Some(gas_price))?,
+        )
         .set("steps", steps)
         .set("gasUsed", gas_used)
         .set("gasCmds", gas_cmds)
@@ -92,7 +103,14 @@ pub fn execute(account: &str, tx: &str, config: &str) -> Result<JsValue, JsValue
         .build())
 }
 
-pub fn make_transaction(data: ton_block::Transaction) -> Result<JsValue, JsValue> {
+/// Converts the transaction into the debugger output.
+///
+/// `totalFees` includes the forwarding fees left in the outbound messages of every
+/// transaction with an action phase, not only of the ordinary ones.
+pub fn make_transaction(
+    data: ton_block::Transaction,
+    gas_price: Option<u64>,
+) -> Result<JsValue, JsValue> {
     fn make_account_status(status: ton_block::AccountStatus) -> JsValue {
         JsValue::from_str(match status {
             ton_block::AccountStatus::AccStateUninit => "uninit",
@@ -137,31 +155,15 @@ pub fn make_transaction(data: ton_block::Transaction) -> Result<JsValue, JsValue
 
     let info = data.read_description().handle_error()?;
 
-    let mut total_fees = data.total_fees.grams.as_u128() as i128;
+    let mut fees = shared::fees::TransactionFees::compute(&data).handle_error()?;
+    fees.gas_price = gas_price;
     let (exit_code, result_code) = match &info {
         ton_block::TransactionDescr::Ordinary(info) => {
             let exit_code = match &info.compute_ph {
                 ton_block::TrComputePhase::Vm(phase) => Some(phase.exit_code),
                 ton_block::TrComputePhase::Skipped(_) => None,
             };
-            let result_code = match &info.action {
-                Some(phase) => {
-                    total_fees += phase
-                        .total_fwd_fees
-                        .as_ref()
-                        .map(|grams| grams.as_u128() as i128)
-                        .unwrap_or_default();
-                    total_fees -= phase
-                        .total_action_fees
-                        .as_ref()
-                        .map(|grams| grams.as_u128() as i128)
-                        .unwrap_or_default();
-
-                    Some(phase.result_code)
-                }
-                None => None,
-            };
-
+            let result_code = info.action.as_ref().map(|phase| phase.result_code);
             (exit_code, result_code)
         }
         _ => (None, None),
@@ -219,7 +221,8 @@ pub fn make_transaction(data: ton_block::Transaction) -> Result<JsValue, JsValue
         .set("resultCode", result_code)
         .set("origStatus", make_account_status(data.orig_status))
         .set("endStatus", make_account_status(data.end_status))
-        .set("totalFees", total_fees.to_string())
+        .set("totalFees", fees.total_with_forwarding().to_string())
+        .set("fees", fees.build())
         .set("inMessage", in_msg)
         .set(
             "outMessages",
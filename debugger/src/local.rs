@@ -0,0 +1,491 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+use shared::*;
+use ton_abi::{Param, ParamType};
+use ton_block::{Deserializable, Serializable};
+use ton_types::{Cell, SliceData};
+use ton_vm::executor::gas::gas_state::Gas;
+use ton_vm::executor::Engine;
+use ton_vm::stack::integer::IntegerData;
+use ton_vm::stack::savelist::SaveList;
+use ton_vm::stack::{Stack, StackItem};
+use wasm_bindgen::prelude::*;
+
+use crate::parse_cell;
+
+const GAS_LIMIT: i64 = 1_000_000;
+const MESSAGE_VALUE: u128 = 1_000_000_000;
+
+const CRC16: crc::Crc<u16> = crc::Crc::<u16>::new(&crc::CRC_16_XMODEM);
+
+/// Runs the ABI function locally on the account state.
+///
+/// Responsible functions (with `answerId: uint32` as the first input) are called with an
+/// internal message whose answer id is the function output id, so the answer can be decoded
+/// as a regular output. Other functions are called with an unsigned external message.
+///
+/// Capabilities and config params (c7) are taken from the config BOC.
+#[wasm_bindgen(js_name = "runLocal")]
+pub fn run_local(
+    account: &str,
+    config: &str,
+    abi: &str,
+    method: &str,
+    input: &str,
+    now: u32,
+) -> Result<JsValue, JsValue> {
+    let account = parse_account(account).handle_error()?;
+    let config = LocalConfig::parse(config).handle_error()?;
+    let contract = ton_abi::Contract::load(abi.as_bytes()).handle_error()?;
+    let function = contract.function(method).handle_error()?;
+
+    let mut input = match input.trim() {
+        "" => Value::Object(Default::default()),
+        input => serde_json::from_str::<Value>(input).handle_error()?,
+    };
+    let responsible = matches!(
+        function.inputs.first(),
+        Some(Param { name, kind: ParamType::Uint(32) }) if name == "answerId"
+    );
+    if responsible {
+        if let Some(input) = input.as_object_mut() {
+            input.insert("answerId".to_string(), function.output_id.into());
+        }
+    }
+    let tokens =
+        ton_abi::token::Tokenizer::tokenize_all_params(&function.inputs, &input).handle_error()?;
+
+    let (message, selector) = if responsible {
+        let body = function.encode_internal_input(&tokens).handle_error()?;
+        let header = ton_block::InternalMessageHeader {
+            ihr_disabled: true,
+            bounce: false,
+            src: ton_block::MsgAddressIntOrNone::Some(Default::default()),
+            dst: account.address.clone(),
+            value: ton_block::CurrencyCollection::from_grams(
+                ton_block::Grams::new(MESSAGE_VALUE).handle_error()?,
+            ),
+            ..Default::default()
+        };
+        let mut message = ton_block::Message::with_int_header(header);
+        message.set_body(SliceData::load_builder(body).handle_error()?);
+        (message, 0)
+    } else {
+        let mut header = HashMap::new();
+        header.insert(
+            "time".to_string(),
+            ton_abi::TokenValue::Time(now as u64 * 1000),
+        );
+        header.insert("expire".to_string(), ton_abi::TokenValue::Expire(now + 60));
+        let body = function
+            .encode_input(&header, &tokens, false, None, Some(account.address.clone()))
+            .handle_error()?;
+        let mut message =
+            ton_block::Message::with_ext_in_header(ton_block::ExternalInboundMessageHeader {
+                dst: account.address.clone(),
+                ..Default::default()
+            });
+        message.set_body(SliceData::load_builder(body).handle_error()?);
+        (message, -1)
+    };
+
+    let body = message.body().unwrap_or_default();
+    let message_value = if responsible { MESSAGE_VALUE } else { 0 };
+    let mut stack = Stack::new();
+    stack
+        .push(int((account.balance + message_value) as i128))
+        .push(int(message_value as i128))
+        .push(StackItem::Cell(message.serialize().handle_error()?))
+        .push(StackItem::Slice(body))
+        .push(int(selector));
+
+    let result = run(&account, &config, stack, now).handle_error()?;
+
+    let mut output = None;
+    for action in result.actions.iter() {
+        let out_msg = match action {
+            ton_block::OutAction::SendMsg { out_msg, .. } => out_msg,
+            _ => continue,
+        };
+        let body = match out_msg.body() {
+            Some(body) => body,
+            None => continue,
+        };
+        let is_answer = match out_msg.header() {
+            ton_block::CommonMsgInfo::IntMsgInfo(_) => responsible,
+            ton_block::CommonMsgInfo::ExtOutMsgInfo(_) => !responsible,
+            _ => false,
+        };
+        if !is_answer {
+            continue;
+        }
+        if let Ok(tokens) = function.decode_output(body, responsible, false) {
+            let value =
+                ton_abi::token::Detokenizer::detokenize_to_json_value(&tokens).handle_error()?;
+            output = Some(value.to_string());
+            break;
+        }
+    }
+
+    Ok(ObjectBuilder::new()
+        .set("output", output)
+        .set("exitCode", result.exit_code)
+        .set("gasUsed", result.gas_used.to_string())
+        .build())
+}
+
+/// Runs a Fift-style get method with the specified stack arguments.
+///
+/// Arguments are `{ type: 'int' | 'cell' | 'slice' | 'null', value?: string }` objects,
+/// where integers are decimal or `0x`-prefixed hex and cells and slices are base64 BOCs.
+///
+/// With an ABI, the method is looked up in `getters` (ABI 2.3) and then in `functions`.
+/// Arguments can then be passed as a JSON object of the inputs, and the resulting stack
+/// is decoded through the outputs.
+#[wasm_bindgen(js_name = "runGetMethod")]
+pub fn run_get_method(
+    account: &str,
+    config: &str,
+    method: &str,
+    args: &str,
+    now: u32,
+    abi: Option<String>,
+) -> Result<JsValue, JsValue> {
+    let account = parse_account(account).handle_error()?;
+    let config = LocalConfig::parse(config).handle_error()?;
+    let getter = match &abi {
+        Some(abi) => Some(find_getter(abi, method).handle_error()?),
+        None => None,
+    };
+
+    let mut stack = Stack::new();
+    let args = match args.trim() {
+        "" => Value::Array(Vec::new()),
+        args => serde_json::from_str::<Value>(args).handle_error()?,
+    };
+    match (&args, &getter) {
+        (Value::Array(args), _) => {
+            for arg in args {
+                stack.push(parse_stack_item(arg)?);
+            }
+        }
+        (Value::Object(args), Some((inputs, _))) => {
+            for param in inputs {
+                let value = args
+                    .get(&param.name)
+                    .ok_or_else(|| anyhow!("Argument `{}` is required", param.name))
+                    .handle_error()?;
+                stack.push(encode_stack_value(&param.kind, value)?);
+            }
+        }
+        _ => return Err("Invalid get method arguments").handle_error(),
+    }
+    stack.push(int(method_id(method) as i128));
+
+    let result = run(&account, &config, stack, now).handle_error()?;
+
+    let output = match &getter {
+        Some((_, outputs)) if result.exit_code == 0 || result.exit_code == 1 => {
+            let mut output = serde_json::Map::new();
+            let items = result.stack.iter().rev().take(outputs.len()).rev();
+            for (param, item) in outputs.iter().zip(items) {
+                output.insert(param.name.clone(), decode_stack_value(&param.kind, item)?);
+            }
+            Some(Value::Object(output).to_string())
+        }
+        _ => None,
+    };
+
+    Ok(ObjectBuilder::new()
+        .set("output", output)
+        .set(
+            "stack",
+            result
+                .stack
+                .iter()
+                .map(|item| JsValue::from(item.to_string()))
+                .collect::<js_sys::Array>(),
+        )
+        .set("exitCode", result.exit_code)
+        .set("gasUsed", result.gas_used.to_string())
+        .build())
+}
+
+/// Returns inputs and outputs of the ABI getter or function.
+fn find_getter(abi: &str, method: &str) -> Result<(Vec<Param>, Vec<Param>)> {
+    let raw = serde_json::from_str::<Value>(abi)?;
+    let getter = raw["getters"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .find(|getter| getter["name"].as_str() == Some(method));
+    if let Some(getter) = getter {
+        let params = |key: &str| -> Result<Vec<Param>> {
+            match getter.get(key) {
+                Some(params) => Ok(serde_json::from_value(params.clone())?),
+                None => Ok(Vec::new()),
+            }
+        };
+        return Ok((params("inputs")?, params("outputs")?));
+    }
+
+    let contract = ton_abi::Contract::load(abi.as_bytes())?;
+    let function = contract.function(method)?;
+    Ok((function.inputs.clone(), function.outputs.clone()))
+}
+
+struct LocalAccount {
+    address: ton_block::MsgAddressInt,
+    balance: u128,
+    code: Cell,
+    data: Cell,
+}
+
+struct ExecutionResult {
+    exit_code: i32,
+    gas_used: i64,
+    stack: Vec<StackItem>,
+    actions: ton_block::OutActions,
+}
+
+struct LocalConfig {
+    capabilities: u64,
+    params: Option<Cell>,
+}
+
+impl LocalConfig {
+    fn parse(boc: &str) -> Result<Self> {
+        let config = ton_block::ConfigParams::construct_from_base64(boc.trim())?;
+        Ok(Self {
+            capabilities: config.capabilities(),
+            params: config.config_params.data().cloned(),
+        })
+    }
+}
+
+fn parse_account(boc: &str) -> Result<LocalAccount> {
+    let account = ton_block::Account::construct_from_base64(boc.trim())?;
+    let stuff = account
+        .stuff()
+        .ok_or_else(|| anyhow!("Account doesn't exist"))?;
+    match &stuff.storage.state {
+        ton_block::AccountState::AccountActive { state_init } => Ok(LocalAccount {
+            address: stuff.addr.clone(),
+            balance: stuff.storage.balance.grams.as_u128(),
+            code: state_init
+                .code
+                .clone()
+                .ok_or_else(|| anyhow!("Account has no code"))?,
+            data: state_init.data.clone().unwrap_or_default(),
+        }),
+        _ => Err(anyhow!("Account is not active")),
+    }
+}
+
+fn run(
+    account: &LocalAccount,
+    config: &LocalConfig,
+    stack: Stack,
+    now: u32,
+) -> Result<ExecutionResult> {
+    let mut info = ton_vm::smart_contract_info::SmartContractInfo::with_myself(
+        SliceData::load_cell(account.address.serialize()?)?,
+    );
+    info.capabilities = config.capabilities;
+    info.config_params = config.params.clone();
+    info.unix_time = now;
+    info.balance =
+        ton_block::CurrencyCollection::from_grams(ton_block::Grams::new(account.balance)?);
+
+    let mut ctrls = SaveList::new();
+    ctrls.put(4, &mut StackItem::Cell(account.data.clone()))?;
+    ctrls.put(7, &mut info.into_temp_data_item())?;
+
+    let mut engine = Engine::with_capabilities(config.capabilities).setup_with_libraries(
+        SliceData::load_cell(account.code.clone())?,
+        Some(ctrls),
+        Some(stack),
+        Some(Gas::new(GAS_LIMIT, 0, GAS_LIMIT, 10)),
+        Vec::new(),
+    );
+
+    let exit_code = match engine.execute() {
+        Ok(exit_code) => exit_code,
+        Err(e) => ton_vm::error::tvm_exception_or_custom_code(&e),
+    };
+
+    let actions = match engine.get_actions().as_cell() {
+        Ok(cell) => ton_block::OutActions::construct_from_cell(cell.clone())?,
+        Err(_) => Default::default(),
+    };
+
+    Ok(ExecutionResult {
+        exit_code,
+        gas_used: engine.gas_used(),
+        stack: engine.stack().storage.clone(),
+        actions,
+    })
+}
+
+fn parse_stack_item(arg: &Value) -> Result<StackItem, JsValue> {
+    let value = arg["value"].as_str().unwrap_or_default().trim();
+    Ok(match arg["type"].as_str() {
+        Some("int") => StackItem::integer(parse_integer(value).handle_error()?),
+        Some("cell") => StackItem::Cell(parse_cell(value)?),
+        Some("slice") => StackItem::Slice(SliceData::load_cell(parse_cell(value)?).handle_error()?),
+        Some("null") => StackItem::None,
+        _ => return Err("Unknown stack item type").handle_error(),
+    })
+}
+
+fn encode_stack_value(kind: &ParamType, value: &Value) -> Result<StackItem, JsValue> {
+    let as_str = || match value {
+        Value::String(value) => Ok(value.clone()),
+        Value::Number(value) => Ok(value.to_string()),
+        _ => Err("Expected a string or a number").handle_error(),
+    };
+    Ok(match kind {
+        ParamType::Uint(_)
+        | ParamType::Int(_)
+        | ParamType::VarUint(_)
+        | ParamType::VarInt(_)
+        | ParamType::Token
+        | ParamType::Time
+        | ParamType::Expire => StackItem::integer(parse_integer(&as_str()?).handle_error()?),
+        ParamType::Bool => int(if value.as_bool().unwrap_or_default() {
+            -1
+        } else {
+            0
+        }),
+        ParamType::Address | ParamType::AddressStd => {
+            let address = ton_block::MsgAddressInt::from_str(&as_str()?).handle_error()?;
+            StackItem::Slice(
+                SliceData::load_cell(address.serialize().handle_error()?).handle_error()?,
+            )
+        }
+        ParamType::Cell => StackItem::Cell(parse_cell(&as_str()?)?),
+        ParamType::Optional(_) if value.is_null() => StackItem::None,
+        ParamType::Optional(inner) => encode_stack_value(inner, value)?,
+        ParamType::Tuple(params) => {
+            let mut items = Vec::with_capacity(params.len());
+            for param in params {
+                items.push(encode_stack_value(&param.kind, &value[&param.name])?);
+            }
+            StackItem::tuple(items)
+        }
+        kind => {
+            return Err(format!(
+                "Unsupported stack argument type: {}",
+                kind.type_signature()
+            ))
+            .handle_error()
+        }
+    })
+}
+
+fn decode_stack_value(kind: &ParamType, item: &StackItem) -> Result<Value, JsValue> {
+    Ok(match (kind, item) {
+        (ParamType::Optional(_), StackItem::None) => Value::Null,
+        (ParamType::Optional(inner), item) => decode_stack_value(inner, item)?,
+        (ParamType::Bool, StackItem::Integer(value)) => Value::Bool(!value.is_zero()),
+        (_, StackItem::Integer(value)) => Value::String(value.to_str_radix(10)),
+        (ParamType::Address | ParamType::AddressStd, StackItem::Slice(slice)) => {
+            let address =
+                ton_block::MsgAddressInt::construct_from(&mut slice.clone()).handle_error()?;
+            Value::String(address.to_string())
+        }
+        (_, StackItem::Cell(cell)) => Value::String(base64::encode(
+            ton_types::serialize_toc(cell).handle_error()?,
+        )),
+        (ParamType::Tuple(params), StackItem::Tuple(items)) => {
+            let mut result = serde_json::Map::new();
+            for (param, item) in params.iter().zip(items.iter()) {
+                result.insert(param.name.clone(), decode_stack_value(&param.kind, item)?);
+            }
+            Value::Object(result)
+        }
+        (_, item) => Value::String(item.to_string()),
+    })
+}
+
+fn parse_integer(value: &str) -> Result<IntegerData> {
+    let value = value.trim();
+    let (sign, value) = match value.strip_prefix('-') {
+        Some(value) => ("-", value),
+        None => ("", value),
+    };
+    match value.strip_prefix("0x") {
+        Some(hex) => IntegerData::from_str_radix(&format!("{}{}", sign, hex), 16),
+        None => IntegerData::from_str_radix(&format!("{}{}", sign, value), 10),
+    }
+}
+
+fn int(value: i128) -> StackItem {
+    StackItem::integer(IntegerData::from_str_radix(&value.to_string(), 10).unwrap_or_default())
+}
+
+/// Computes the id of a get method: `(crc16(name) & 0xffff) | 0x10000`.
+fn method_id(name: &str) -> u32 {
+    CRC16.checksum(name.as_bytes()) as u32 | 0x10000
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn round_trip(kind: ParamType, value: Value) -> Value {
+        let item = encode_stack_value(&kind, &value).unwrap();
+        decode_stack_value(&kind, &item).unwrap()
+    }
+
+    #[test]
+    fn get_method_ids() {
+        assert_eq!(method_id("seqno"), 85143);
+        assert_eq!(method_id("get_public_key"), 78748);
+        assert_eq!(
+            method_id("seqno"),
+            CRC16.checksum(b"seqno") as u32 | 0x10000
+        );
+    }
+
+    #[test]
+    fn integers() {
+        let parse = |value: &str| parse_integer(value).unwrap().to_str_radix(10);
+        assert_eq!(parse("42"), "42");
+        assert_eq!(parse(" -42 "), "-42");
+        assert_eq!(parse("0xff"), "255");
+        assert_eq!(parse("-0x10"), "-16");
+        assert!(parse_integer("0xzz").is_err());
+    }
+
+    #[test]
+    fn stack_values() {
+        let address = format!("0:{}", "33".repeat(32));
+
+        assert_eq!(round_trip(ParamType::Uint(128), json!("123")), json!("123"));
+        assert_eq!(round_trip(ParamType::Int(32), json!(-5)), json!("-5"));
+        assert_eq!(round_trip(ParamType::Token, json!("0x10")), json!("16"));
+        assert_eq!(round_trip(ParamType::Bool, json!(true)), json!(true));
+        assert_eq!(round_trip(ParamType::Bool, json!(false)), json!(false));
+        assert_eq!(
+            round_trip(ParamType::Address, json!(address)),
+            json!(address)
+        );
+
+        let optional = ParamType::Optional(Box::new(ParamType::Uint(8)));
+        assert_eq!(round_trip(optional.clone(), Value::Null), Value::Null);
+        assert_eq!(round_trip(optional, json!("7")), json!("7"));
+
+        let tuple = ParamType::Tuple(vec![
+            Param::new("value", ParamType::Uint(64)),
+            Param::new("flag", ParamType::Bool),
+        ]);
+        let value = json!({ "value": "1000", "flag": true });
+        assert_eq!(round_trip(tuple, value.clone()), value);
+    }
+}
@@ -0,0 +1,300 @@
+use anyhow::Result;
+use ton_block::{
+    CommonMsgInfo, ConfigParams, Deserializable, TrActionPhase, TrBouncePhase, TrComputePhase,
+    TrCreditPhase, TrStoragePhase, Transaction, TransactionDescr,
+};
+use wasm_bindgen::JsValue;
+
+use crate::ObjectBuilder;
+
+#[derive(Debug, Clone, Default)]
+pub struct TransactionFees {
+    /// Fees collected by the transaction (`total_fees` field).
+    pub total: u128,
+    pub storage: u128,
+    pub storage_due: u128,
+    pub credit: u128,
+    pub credited_value: u128,
+    pub compute: u128,
+    pub gas_used: u64,
+    pub gas_limit: u64,
+    /// Gas price from the config param 20 or 21, in nanotokens per gas unit.
+    pub gas_price: Option<u64>,
+    pub action: u128,
+    pub forward: u128,
+    pub bounce: u128,
+    pub bounce_forward: u128,
+    pub import: u128,
+    pub out_messages: Vec<OutMessageFees>,
+}
+
+#[derive(Debug, Clone)]
+pub struct OutMessageFees {
+    pub hash: ton_types::UInt256,
+    pub value: u128,
+    pub fwd_fee: u128,
+}
+
+impl TransactionFees {
+    pub fn compute(tx: &Transaction) -> Result<Self> {
+        let mut fees = Self {
+            total: tx.total_fees.grams.as_u128(),
+            ..Default::default()
+        };
+
+        match tx.read_description()? {
+            TransactionDescr::Ordinary(descr) => {
+                fees.add_storage(descr.storage_ph.as_ref());
+                fees.add_credit(descr.credit_ph.as_ref());
+                fees.add_compute(&descr.compute_ph);
+                fees.add_action(descr.action.as_ref());
+                fees.add_bounce(descr.bounce.as_ref());
+            }
+            TransactionDescr::Storage(descr) => fees.add_storage(Some(&descr.storage_ph)),
+            TransactionDescr::TickTock(descr) => {
+                fees.add_storage(Some(&descr.storage));
+                fees.add_compute(&descr.compute_ph);
+                fees.add_action(descr.action.as_ref());
+            }
+            TransactionDescr::SplitPrepare(descr) => {
+                fees.add_storage(descr.storage_ph.as_ref());
+                fees.add_compute(&descr.compute_ph);
+                fees.add_action(descr.action.as_ref());
+            }
+            TransactionDescr::SplitInstall(_) => {}
+            TransactionDescr::MergePrepare(descr) => fees.add_storage(Some(&descr.storage_ph)),
+            TransactionDescr::MergeInstall(descr) => {
+                fees.add_storage(descr.storage_ph.as_ref());
+                fees.add_credit(descr.credit_ph.as_ref());
+                fees.add_compute(&descr.compute_ph);
+                fees.add_action(descr.action.as_ref());
+            }
+        }
+
+        if let Some(in_msg) = tx.read_in_msg()? {
+            if let CommonMsgInfo::ExtInMsgInfo(header) = in_msg.header() {
+                fees.import = header.import_fee.as_u128();
+            }
+        }
+
+        tx.out_msgs.iterate_slices(|slice| {
+            let cell = slice.reference(0)?;
+            let hash = cell.repr_hash();
+            let message = ton_block::Message::construct_from_cell(cell)?;
+            let (value, fwd_fee) = match message.header() {
+                CommonMsgInfo::IntMsgInfo(header) => {
+                    (header.value.grams.as_u128(), header.fwd_fee.as_u128())
+                }
+                _ => (0, 0),
+            };
+            fees.out_messages.push(OutMessageFees {
+                hash,
+                value,
+                fwd_fee,
+            });
+            Ok(true)
+        })?;
+
+        Ok(fees)
+    }
+
+    /// Reads the gas price in nanotokens per gas unit from the config.
+    pub fn gas_price(config: &ConfigParams, is_masterchain: bool) -> Result<u64> {
+        // The config stores the price in 1/65536 of a nanotoken
+        Ok(config.gas_prices(is_masterchain)?.gas_price >> 16)
+    }
+
+    /// Total fees including the forwarding fees which are left in the outbound messages.
+    ///
+    /// The action fees are the part of the forwarding fees which is already collected
+    /// into `total`, so only the remainder is added.
+    pub fn total_with_forwarding(&self) -> u128 {
+        self.total + self.forward.saturating_sub(self.action)
+    }
+
+    pub fn build(&self) -> JsValue {
+        let out_messages = self
+            .out_messages
+            .iter()
+            .map(|message| {
+                ObjectBuilder::new()
+                    .set("hash", message.hash.to_hex_string())
+                    .set("value", message.value.to_string())
+                    .set("fwdFee", message.fwd_fee.to_string())
+                    .build()
+            })
+            .collect::<js_sys::Array>();
+
+        ObjectBuilder::new()
+            .set("total", self.total.to_string())
+            .set(
+                "totalWithForwarding",
+                self.total_with_forwarding().to_string(),
+            )
+            .set("storage", self.storage.to_string())
+            .set("storageDue", self.storage_due.to_string())
+            .set("credit", self.credit.to_string())
+            .set("creditedValue", self.credited_value.to_string())
+            .set("compute", self.compute.to_string())
+            .set("gasUsed", self.gas_used.to_string())
+            .set("gasLimit", self.gas_limit.to_string())
+            .set("gasPrice", self.gas_price.map(|price| price.to_string()))
+            .set("action", self.action.to_string())
+            .set("forward", self.forward.to_string())
+            .set("bounce", self.bounce.to_string())
+            .set("bounceForward", self.bounce_forward.to_string())
+            .set("import", self.import.to_string())
+            .set("outMessages", out_messages)
+            .build()
+    }
+
+    fn add_storage(&mut self, phase: Option<&TrStoragePhase>) {
+        if let Some(phase) = phase {
+            self.storage += phase.storage_fees_collected.as_u128();
+            if let Some(due) = &phase.storage_fees_due {
+                self.storage_due += due.as_u128();
+            }
+        }
+    }
+
+    fn add_credit(&mut self, phase: Option<&TrCreditPhase>) {
+        if let Some(phase) = phase {
+            if let Some(collected) = &phase.due_fees_collected {
+                self.credit += collected.as_u128();
+            }
+            self.credited_value += phase.credit.grams.as_u128();
+        }
+    }
+
+    fn add_compute(&mut self, phase: &TrComputePhase) {
+        if let TrComputePhase::Vm(phase) = phase {
+            self.compute += phase.gas_fees.as_u128();
+            self.gas_used += phase.gas_used.as_u64();
+            self.gas_limit += phase.gas_limit.as_u64();
+        }
+    }
+
+    fn add_action(&mut self, phase: Option<&TrActionPhase>) {
+        if let Some(phase) = phase {
+            if let Some(fees) = &phase.total_action_fees {
+                self.action += fees.as_u128();
+            }
+            if let Some(fees) = &phase.total_fwd_fees {
+                self.forward += fees.as_u128();
+            }
+        }
+    }
+
+    fn add_bounce(&mut self, phase: Option<&TrBouncePhase>) {
+        if let Some(TrBouncePhase::Ok(phase)) = phase {
+            self.bounce += phase.msg_fees.as_u128();
+            self.bounce_forward += phase.fwd_fees.as_u128();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ton_block::{
+        AccountStatus, CurrencyCollection, Grams, Serializable, TrComputePhaseVm,
+        TransactionDescrOrdinary, TransactionDescrTickTock, TransactionTickTock, VarUInteger7,
+    };
+    use ton_types::{AccountId, UInt256};
+
+    use super::*;
+
+    fn make_transaction(total: u64, descr: TransactionDescr) -> Transaction {
+        let mut tx = Transaction::with_address_and_status(
+            AccountId::from(UInt256::default()),
+            AccountStatus::AccStateActive,
+        );
+        tx.set_total_fees(CurrencyCollection::with_grams(total));
+        tx.write_description(&descr).unwrap();
+        Transaction::construct_from_cell(tx.serialize().unwrap()).unwrap()
+    }
+
+    fn make_compute_phase(gas_fees: u64, gas_used: u64) -> TrComputePhase {
+        TrComputePhase::Vm(TrComputePhaseVm {
+            success: true,
+            gas_fees: Grams::new(gas_fees as u128).unwrap(),
+            gas_used: VarUInteger7::new(gas_used).unwrap(),
+            gas_limit: VarUInteger7::new(1_000_000).unwrap(),
+            ..Default::default()
+        })
+    }
+
+    fn make_action_phase(action: u64, forward: u64) -> TrActionPhase {
+        TrActionPhase {
+            success: true,
+            valid: true,
+            total_action_fees: Some(Grams::new(action as u128).unwrap()),
+            total_fwd_fees: Some(Grams::new(forward as u128).unwrap()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn ordinary_transaction() {
+        let tx = make_transaction(
+            5_000_000,
+            TransactionDescr::Ordinary(TransactionDescrOrdinary {
+                storage_ph: Some(TrStoragePhase {
+                    storage_fees_collected: Grams::new(1_000).unwrap(),
+                    ..Default::default()
+                }),
+                compute_ph: make_compute_phase(3_000_000, 3_000),
+                action: Some(make_action_phase(333_333, 1_000_000)),
+                ..Default::default()
+            }),
+        );
+
+        let fees = TransactionFees::compute(&tx).unwrap();
+        assert_eq!(fees.total, 5_000_000);
+        assert_eq!(fees.storage, 1_000);
+        assert_eq!(fees.compute, 3_000_000);
+        assert_eq!(fees.gas_used, 3_000);
+        assert_eq!(fees.gas_limit, 1_000_000);
+        assert_eq!(fees.action, 333_333);
+        assert_eq!(fees.forward, 1_000_000);
+        assert_eq!(fees.import, 0);
+        assert!(fees.out_messages.is_empty());
+        assert_eq!(fees.total_with_forwarding(), 5_666_667);
+    }
+
+    #[test]
+    fn tick_tock_transaction() {
+        let tx = make_transaction(
+            2_000_000,
+            TransactionDescr::TickTock(TransactionDescrTickTock {
+                tt: TransactionTickTock::Tock,
+                compute_ph: make_compute_phase(2_000_000, 2_000),
+                action: Some(make_action_phase(0, 0)),
+                ..Default::default()
+            }),
+        );
+
+        let fees = TransactionFees::compute(&tx).unwrap();
+        assert_eq!(fees.compute, 2_000_000);
+        assert_eq!(fees.gas_used, 2_000);
+        assert_eq!(fees.total_with_forwarding(), 2_000_000);
+    }
+
+    #[test]
+    fn total_with_forwarding() {
+        let fees = TransactionFees {
+            total: 100,
+            action: 10,
+            forward: 30,
+            ..Default::default()
+        };
+        assert_eq!(fees.total_with_forwarding(), 120);
+
+        // Action fees without forwarding fees never reduce the collected fees
+        let fees = TransactionFees {
+            total: 100,
+            action: 10,
+            ..Default::default()
+        };
+        assert_eq!(fees.total_with_forwarding(), 100);
+    }
+}
@@ -1,5 +1,7 @@
 use wasm_bindgen::JsValue;
 
+pub mod fees;
+
 pub struct ObjectBuilder {
     object: js_sys::Object,
 }
@@ -4,52 +4,6 @@ import BigNumber from 'bignumber.js';
 import * as core from '@core';
 import { useTvmConnect } from './useTvmConnect';
 
-const FACTORY_BOC =
-  'te6ccgEBDQEA4gACATQDAQEBwAIAQ9AAAAAACAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAACACKP8AIMEB9KQgWJL0oOBfAoog7VPZBgQBCvSkIPShBQAAAgEgDAcCAv0KCAIBIAsJALcAe1AAfhhAdMAAcAAjjsw0z/TH9MfghAnss0dErryqfgAcPhk1fpA1NHIgBDPCwUSznD6AnbPC2vMyYEAoPsAXwPtUIIQJ7LNHSBZAVUB4IECABLXGAEwIVUB2YAIBIAsLAAU8jaAANN8wIPhh0NMAAcAAkvIw4dYB0wAwwADyafI3';
-const MICROWAVE_BOC =
-  'te6ccgEBCQEA3gACATQDAQEBwAIAQ9AAAAAACAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAACACKP8AIMEB9KQgWJL0oOBfAoog7VPZBgQBCvSkIPShBQAAAQPQQAcB/gHQ0wABwADysNYB0wAwwADyvDAgxwGa7UDtUHADXwPbMAHAAI5QMAHTH4IQU8UVHyIBuZcwwADyfPI84IIQU8UVHxK68ryCEDuaygBw+wLIgBDPCwUB+kACznD6AnbPC2sB1DABzMmBAID7ACBwcFkBVQFVAtkgWQFVAeAixwIIAAzAACIiAeI=';
-
-const FACTORY_ABI = {
-  'ABI version': 2,
-  version: '2.2',
-  header: ['time', 'expire'],
-  functions: [
-    {
-      name: 'constructor',
-      inputs: [
-        { name: 'dest', type: 'address' },
-        { name: 'state_init', type: 'cell' }
-      ],
-      outputs: []
-    }
-  ],
-  fields: [{ name: '__uninitialized', type: 'bool' }],
-  events: []
-} as const;
-
-const MICROWAVE_ABI = {
-  'ABI version': 2,
-  version: '2.2',
-  functions: [
-    {
-      name: 'deploy',
-      inputs: [
-        {
-          name: 'dest',
-          type: 'address'
-        },
-        {
-          name: 'state_init',
-          type: 'cell'
-        }
-      ],
-      outputs: []
-    }
-  ],
-  events: [],
-  headers: []
-} as const;
-
 const initialized = ref(false);
 const microwaveReady = ref<boolean>();
 
@@ -63,14 +17,10 @@ type Addresses = {
 const getAddresses = async (provider: ProviderRpcClient): Promise<Addresses | undefined> => {
   await provider.ensureInitialized()
 
-  const [{ hash: factoryHash }, { hash: microwaveHash }] = await Promise.all([
-    provider.rawApi.getBocHash({ boc: FACTORY_BOC }),
-    provider.rawApi.getBocHash({ boc: MICROWAVE_BOC })
-  ]);
-
+  const contracts = core.getMicrowaveContracts();
   return {
-    factory: `0:${factoryHash}`,
-    microwave: `0:${microwaveHash}`
+    factory: contracts.factoryAddress,
+    microwave: contracts.microwaveAddress
   };
 }
 
@@ -204,6 +154,14 @@ const unfreezeContract = (args: UnfreezeContractParams, setStatus: (status?: str
     if (stateInit == null) {
       throw new Error('Failed to prepare state');
     }
+    const verification = core.verifyFrozenState(accountState.boc, stateInit);
+    if (!verification.matches) {
+      throw new Error(
+        verification.conclusive
+          ? `Restored state doesn't match the frozen state: ${verification.differences.join(', ')} differ`
+          : "Restored state doesn't match the frozen state hash"
+      );
+    }
 
     setStatus('creating subscriptions');
     const [microwaveSubscription, targetSubscription] = await Promise.all([
@@ -252,16 +210,13 @@ const unfreezeContract = (args: UnfreezeContractParams, setStatus: (status?: str
       throw new Error('Account not selected');
     }
 
-    const walletTx = await new provider.Contract(MICROWAVE_ABI, microwaveAddress).methods
-      .deploy({
-        dest: accountAddress,
-        state_init: stateInit
-      })
-      .send({
-        from,
-        amount: amount.toFixed(),
-        bounce: false
-      });
+    const { transaction: walletTx } = await provider.sendMessage({
+      sender: from,
+      recipient: microwaveAddress,
+      amount: amount.toFixed(),
+      bounce: core.getMicrowaveContracts().deployBounce,
+      payload: core.encodeMicrowaveDeploy(accountAddress.toString(), stateInit)
+    });
     if (state.cancelled) {
       return;
     }
@@ -416,15 +371,12 @@ const deployMicrowave = (setStatus: (status?: string) => void) => {
 
     setStatus('deploying factory')
 
-    await new provider.Contract(FACTORY_ABI, factoryAddress).methods
-      .constructor({
-        dest: new Address(addresses.microwave),
-        state_init: MICROWAVE_BOC
-      })
-      .sendExternal({
-        withoutSignature: true,
-        stateInit: FACTORY_BOC
-      });
+    const time = Date.now();
+    await provider.sendUnsignedExternalMessage({
+      recipient: factoryAddress,
+      stateInit: core.getMicrowaveContracts().factoryBoc,
+      payload: core.encodeFactoryDeploy(time, Math.floor(time / 1000) + 60)
+    });
     if (state.cancelled) {
       return;
     }